@@ -0,0 +1,84 @@
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use funpiler::parser::{self, ParseOptions, Parser, Result, ZeroOrMore};
+
+const ITERATIONS: u32 = 20;
+
+fn bench<T>(name: &str, mut f: impl FnMut() -> T) {
+    // Warm up once so the first measured run isn't paying for cold caches.
+    black_box(f());
+
+    let mut total = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        black_box(f());
+        total += start.elapsed();
+    }
+
+    println!("{name:<24} {:>12.3?} / iter", total / ITERATIONS);
+}
+
+fn program(statements: usize) -> String {
+    let mut source = String::new();
+    for idx in 0..statements {
+        match idx % 4 {
+            0 => source.push_str(&format!(
+                "var x{idx} = {idx} * (y + 0x{idx:x}) - f(a, b, c);\n"
+            )),
+            1 => source.push_str(&format!(
+                "if (x{idx} != {idx}) {{ x = x + 1; }} else {{ x = 0; }}\n"
+            )),
+            2 => source.push_str(&format!(
+                "function f{idx}(a, b) {{ while (a != b) {{ a = a + 1; }} return [a, b]; }}\n"
+            )),
+            _ => source.push_str(&format!("// comment {idx}\nprint(x{idx} << 2 | ~{idx});\n")),
+        }
+    }
+
+    source
+}
+
+fn sum(terms: usize, term: &str) -> String {
+    vec![term; terms].join(" + ")
+}
+
+fn letter(source: &str) -> Option<Result<'_, char>> {
+    let ch = source.chars().next().filter(char::is_ascii_alphabetic)?;
+    Some(Result {
+        value: ch,
+        source: &source[ch.len_utf8()..],
+    })
+}
+
+fn main() {
+    let program = program(4000);
+    assert!(parser::parse_program(&program).is_ok());
+    bench("program (4000 stmts)", || parser::parse_program(&program));
+
+    let memoized = ParseOptions {
+        memoize: true,
+        ..Default::default()
+    };
+    bench("program memoized", || memoized.parse_program(&program));
+
+    let ids = sum(5000, "identifier_name");
+    bench("id (5000 terms)", || parser::parse_expression(&ids));
+
+    let numbers = sum(5000, "1234567");
+    bench("number (5000 terms)", || parser::parse_expression(&numbers));
+
+    let args = (0..5000)
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let call = format!("f({args})");
+    bench("arguments (5000 args)", || parser::parse_expression(&call));
+
+    let letters = "a".repeat(100_000);
+    bench("zero_or_more (100k)", || {
+        ZeroOrMore::new(letter).parse(&letters)
+    });
+}
@@ -1,40 +1,586 @@
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg(test)]
+mod tests;
+
+mod json;
+mod source;
+
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+};
+
+#[cfg(feature = "bigint")]
+use crate::bigint::BigInt;
+use crate::ranges::IntWidth;
+
+pub use json::to_json;
+pub use source::{PrintOptions, to_source};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
 pub enum Node {
     Number(i64),
+    /// Integer literal that doesn't fit in an `i64`.
+    #[cfg(feature = "bigint")]
+    BigNumber(BigInt),
+    /// An integer literal with a width suffix, such as `255u8`.
+    TypedNumber {
+        value: i64,
+        suffix: IntWidth,
+    },
+    Char(char),
+    String(String),
+    Interpolation(Vec<StringPart>),
     Id(String),
     Not(Box<Node>),
+    Negate(Box<Node>),
     Equal(Box<Node>, Box<Node>),
     NotEqual(Box<Node>, Box<Node>),
     Add(Box<Node>, Box<Node>),
     Subtract(Box<Node>, Box<Node>),
     Multiply(Box<Node>, Box<Node>),
     Divide(Box<Node>, Box<Node>),
-    Call { callee: String, args: Vec<Node> },
+    /// Integer division, whatever the interpreter's `DivisionMode`. See
+    /// `ParseOptions::int_division` for when `//` parses.
+    IntDivide(Box<Node>, Box<Node>),
+    Modulo(Box<Node>, Box<Node>),
+    BitNot(Box<Node>),
+    BitAnd(Box<Node>, Box<Node>),
+    BitOr(Box<Node>, Box<Node>),
+    BitXor(Box<Node>, Box<Node>),
+    ShiftLeft(Box<Node>, Box<Node>),
+    ShiftRight(Box<Node>, Box<Node>),
+    Power(Box<Node>, Box<Node>),
+    Call {
+        callee: Box<Node>,
+        args: Vec<Node>,
+    },
+    Index {
+        target: Box<Node>,
+        index: Box<Node>,
+    },
+    Member {
+        target: Box<Node>,
+        field: String,
+    },
+    Array(Vec<Node>),
+    /// Either `()` or at least two elements, as a single expression in
+    /// parentheses is just that expression.
+    Tuple(Vec<Node>),
+    /// Map literal entries as `(key, value)` pairs, in source order.
+    Map(Vec<(Node, Node)>),
     Return(Box<Node>),
     Block(Vec<Node>),
+    /// Block ending in an expression without a semicolon, `{ s; value }`.
+    /// Used as an expression, it evaluates to `value`.
+    BlockExpression {
+        statements: Vec<Node>,
+        value: Box<Node>,
+    },
     If(If),
     Function(Function),
+    Lambda {
+        parameters: Vec<String>,
+        body: Box<Node>,
+    },
     Var(String, Box<Node>),
+    /// `var (a, b) = value;`, declaring a variable for each element of the
+    /// tuple `value` evaluates to.
+    Destructure {
+        pattern: Vec<String>,
+        value: Box<Node>,
+    },
     Assignment(String, Box<Node>),
     While(While),
+    /// Placeholder for an item that failed to parse.
+    Error,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl Node {
+    pub fn children(&self) -> Vec<&Node> {
+        match self {
+            Node::Number(_)
+            | Node::TypedNumber { .. }
+            | Node::Char(_)
+            | Node::String(_)
+            | Node::Id(_)
+            | Node::Error => vec![],
+            #[cfg(feature = "bigint")]
+            Node::BigNumber(_) => vec![],
+            Node::Not(term) | Node::Negate(term) | Node::BitNot(term) | Node::Return(term) => {
+                vec![term]
+            }
+            Node::Member { target, .. } => vec![target],
+            Node::Lambda { body, .. } => vec![body],
+            Node::Interpolation(parts) => parts
+                .iter()
+                .filter_map(|part| match part {
+                    StringPart::Literal(_) => None,
+                    StringPart::Expression(node) => Some(node),
+                })
+                .collect(),
+            Node::Var(_, value) | Node::Destructure { value, .. } | Node::Assignment(_, value) => {
+                vec![value]
+            }
+            Node::Equal(left, right)
+            | Node::NotEqual(left, right)
+            | Node::Add(left, right)
+            | Node::Subtract(left, right)
+            | Node::Multiply(left, right)
+            | Node::Divide(left, right)
+            | Node::IntDivide(left, right)
+            | Node::Modulo(left, right)
+            | Node::BitAnd(left, right)
+            | Node::BitOr(left, right)
+            | Node::BitXor(left, right)
+            | Node::ShiftLeft(left, right)
+            | Node::ShiftRight(left, right)
+            | Node::Power(left, right) => vec![left, right],
+            Node::Call { callee, args } => std::iter::once(callee.as_ref()).chain(args).collect(),
+            Node::Index { target, index } => vec![target, index],
+            Node::Array(nodes) | Node::Tuple(nodes) | Node::Block(nodes) => nodes.iter().collect(),
+            Node::BlockExpression { statements, value } => {
+                statements.iter().chain([value.as_ref()]).collect()
+            }
+            Node::Map(entries) => entries
+                .iter()
+                .flat_map(|(key, value)| [key, value])
+                .collect(),
+            Node::If(If {
+                condition,
+                consequence,
+                alternative,
+            }) => vec![condition, consequence, alternative],
+            Node::Function(Function { body, .. }) => vec![body],
+            Node::While(While {
+                condition,
+                body,
+                alternative,
+            }) => {
+                let mut children = vec![condition.as_ref(), body];
+                children.extend(alternative.as_deref());
+                children
+            }
+        }
+    }
+
+    /// Same as `children`, in the same order.
+    pub fn children_mut(&mut self) -> Vec<&mut Node> {
+        match self {
+            Node::Number(_)
+            | Node::TypedNumber { .. }
+            | Node::Char(_)
+            | Node::String(_)
+            | Node::Id(_)
+            | Node::Error => vec![],
+            #[cfg(feature = "bigint")]
+            Node::BigNumber(_) => vec![],
+            Node::Not(term) | Node::Negate(term) | Node::BitNot(term) | Node::Return(term) => {
+                vec![term]
+            }
+            Node::Member { target, .. } => vec![target],
+            Node::Lambda { body, .. } => vec![body],
+            Node::Interpolation(parts) => parts
+                .iter_mut()
+                .filter_map(|part| match part {
+                    StringPart::Literal(_) => None,
+                    StringPart::Expression(node) => Some(node),
+                })
+                .collect(),
+            Node::Var(_, value) | Node::Destructure { value, .. } | Node::Assignment(_, value) => {
+                vec![value]
+            }
+            Node::Equal(left, right)
+            | Node::NotEqual(left, right)
+            | Node::Add(left, right)
+            | Node::Subtract(left, right)
+            | Node::Multiply(left, right)
+            | Node::Divide(left, right)
+            | Node::IntDivide(left, right)
+            | Node::Modulo(left, right)
+            | Node::BitAnd(left, right)
+            | Node::BitOr(left, right)
+            | Node::BitXor(left, right)
+            | Node::ShiftLeft(left, right)
+            | Node::ShiftRight(left, right)
+            | Node::Power(left, right) => vec![left, right],
+            Node::Call { callee, args } => std::iter::once(callee.as_mut()).chain(args).collect(),
+            Node::Index { target, index } => vec![target, index],
+            Node::Array(nodes) | Node::Tuple(nodes) | Node::Block(nodes) => {
+                nodes.iter_mut().collect()
+            }
+            Node::BlockExpression { statements, value } => {
+                statements.iter_mut().chain([value.as_mut()]).collect()
+            }
+            Node::Map(entries) => entries
+                .iter_mut()
+                .flat_map(|(key, value)| [key, value])
+                .collect(),
+            Node::If(If {
+                condition,
+                consequence,
+                alternative,
+            }) => vec![condition, consequence, alternative],
+            Node::Function(Function { body, .. }) => vec![body],
+            Node::While(While {
+                condition,
+                body,
+                alternative,
+            }) => {
+                let mut children = vec![condition.as_mut(), body];
+                children.extend(alternative.as_deref_mut());
+                children
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct If {
     pub condition: Box<Node>,
     pub consequence: Box<Node>,
     pub alternative: Box<Node>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Function {
     pub name: String,
     pub parameters: Vec<String>,
     pub body: Box<Node>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StringPart {
+    Literal(String),
+    Expression(Node),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct While {
     pub condition: Box<Node>,
     pub body: Box<Node>,
+    pub alternative: Option<Box<Node>>,
+}
+
+impl Node {
+    fn precedence(&self) -> u8 {
+        match self {
+            Node::BitOr(..) => 1,
+            Node::BitXor(..) => 2,
+            Node::BitAnd(..) => 3,
+            Node::Equal(..) | Node::NotEqual(..) => 4,
+            Node::ShiftLeft(..) | Node::ShiftRight(..) => 5,
+            Node::Add(..) | Node::Subtract(..) => 6,
+            Node::Multiply(..) | Node::Divide(..) | Node::IntDivide(..) | Node::Modulo(..) => 7,
+            Node::Not(_) | Node::Negate(_) | Node::BitNot(_) => 8,
+            Node::Power(..) => 9,
+            _ => 10,
+        }
+    }
+
+    /// Adds every variable, function and parameter name in the tree to
+    /// `names`.
+    pub(crate) fn collect_names(&self, names: &mut HashSet<String>) {
+        match self {
+            Node::Id(name) | Node::Var(name, _) | Node::Assignment(name, _) => {
+                names.insert(name.clone());
+            }
+            Node::Lambda { parameters, .. }
+            | Node::Destructure {
+                pattern: parameters,
+                ..
+            } => names.extend(parameters.iter().cloned()),
+            Node::Function(Function {
+                name, parameters, ..
+            }) => {
+                names.insert(name.clone());
+                names.extend(parameters.iter().cloned());
+            }
+            _ => (),
+        }
+
+        for child in self.children() {
+            child.collect_names(names);
+        }
+    }
+
+    pub(crate) fn is_statement(&self) -> bool {
+        matches!(
+            self,
+            Node::Return(_)
+                | Node::Block(_)
+                | Node::BlockExpression { .. }
+                | Node::If(_)
+                | Node::Function(_)
+                | Node::Var(..)
+                | Node::Destructure { .. }
+                | Node::Assignment(..)
+                | Node::While(_)
+                | Node::Error
+        )
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, precedence: u8) -> fmt::Result {
+        if self.precedence() < precedence {
+            write!(f, "({self})")
+        } else {
+            write!(f, "{self}")
+        }
+    }
+
+    fn fmt_binary(
+        f: &mut fmt::Formatter<'_>,
+        operator: &str,
+        precedence: u8,
+        left: &Node,
+        right: &Node,
+    ) -> fmt::Result {
+        left.fmt_operand(f, precedence)?;
+        write!(f, " {operator} ")?;
+        right.fmt_operand(f, precedence + 1)
+    }
+
+    // Formats a statement that is followed by an `else`, spelling out empty
+    // alternatives so that the `else` can't bind to a nested `if`.
+    fn fmt_closed(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Node::If(If {
+                condition,
+                consequence,
+                alternative,
+            }) => {
+                write!(f, "if ({condition}) ")?;
+                consequence.fmt_closed(f)?;
+                write!(f, " else ")?;
+                alternative.fmt_closed(f)
+            }
+            Node::While(While {
+                condition,
+                body,
+                alternative,
+            }) => {
+                write!(f, "while ({condition}) ")?;
+                body.fmt_closed(f)?;
+                match alternative {
+                    Some(alternative) => write!(f, " else {alternative}"),
+                    None => write!(f, " else {{ }}"),
+                }
+            }
+            _ => self.fmt_statement(f),
+        }
+    }
+
+    // A block with a value takes a `;` too, or at the end of a block it would
+    // be that block's value.
+    fn fmt_statement(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_statement() && !matches!(self, Node::BlockExpression { .. }) {
+            write!(f, "{self}")
+        } else {
+            write!(f, "{self};")
+        }
+    }
+}
+
+fn fmt_list(f: &mut fmt::Formatter<'_>, items: &[impl Display]) -> fmt::Result {
+    for (idx, item) in items.iter().enumerate() {
+        if idx > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{item}")?;
+    }
+
+    Ok(())
+}
+
+fn escape_char(ch: char, quote: char) -> String {
+    match ch {
+        '\n' => "\\n".to_string(),
+        '\t' => "\\t".to_string(),
+        '\r' => "\\r".to_string(),
+        '\0' => "\\0".to_string(),
+        '\\' => "\\\\".to_string(),
+        ch if ch == quote => format!("\\{ch}"),
+        ch if ch.is_ascii_control() => format!("\\x{:02x}", ch as u32),
+        ch => ch.to_string(),
+    }
+}
+
+/// Renders a name for a diagnostic that quotes it in backticks, escaping
+/// control characters so they can't garble the output.
+pub fn escape_identifier(name: &str) -> String {
+    name.chars().map(|ch| escape_char(ch, '`')).collect()
+}
+
+pub fn escape_string(value: &str) -> String {
+    let mut escaped = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(ch) = chars.next() {
+        // `${` would start an interpolation when parsed back.
+        if ch == '$' && chars.peek() == Some(&'{') {
+            escaped.push_str("\\$");
+        } else {
+            escaped.push_str(&escape_char(ch, '"'));
+        }
+    }
+
+    escaped
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precedence = self.precedence();
+
+        match self {
+            Node::Number(value) => write!(f, "{value}"),
+            #[cfg(feature = "bigint")]
+            Node::BigNumber(value) => write!(f, "{value}"),
+            Node::TypedNumber { value, suffix } => write!(f, "{value}{suffix}"),
+            Node::Char(value) => write!(f, "'{}'", escape_char(*value, '\'')),
+            Node::String(value) => write!(f, "\"{}\"", escape_string(value)),
+            Node::Interpolation(parts) => {
+                write!(f, "\"")?;
+                for part in parts {
+                    match part {
+                        StringPart::Literal(text) => write!(f, "{}", escape_string(text))?,
+                        StringPart::Expression(node) => write!(f, "${{{node}}}")?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Node::Id(name) => write!(f, "{name}"),
+            Node::Not(term) => {
+                write!(f, "!")?;
+                term.fmt_operand(f, precedence)
+            }
+            Node::Negate(term) => {
+                write!(f, "-")?;
+                term.fmt_operand(f, precedence)
+            }
+            Node::BitNot(term) => {
+                write!(f, "~")?;
+                term.fmt_operand(f, precedence)
+            }
+            Node::Equal(left, right) => Node::fmt_binary(f, "==", precedence, left, right),
+            Node::NotEqual(left, right) => Node::fmt_binary(f, "!=", precedence, left, right),
+            Node::Add(left, right) => Node::fmt_binary(f, "+", precedence, left, right),
+            Node::Subtract(left, right) => Node::fmt_binary(f, "-", precedence, left, right),
+            Node::Multiply(left, right) => Node::fmt_binary(f, "*", precedence, left, right),
+            Node::Divide(left, right) => Node::fmt_binary(f, "/", precedence, left, right),
+            Node::IntDivide(left, right) => Node::fmt_binary(f, "//", precedence, left, right),
+            Node::Modulo(left, right) => Node::fmt_binary(f, "%", precedence, left, right),
+            Node::BitAnd(left, right) => Node::fmt_binary(f, "&", precedence, left, right),
+            Node::BitOr(left, right) => Node::fmt_binary(f, "|", precedence, left, right),
+            Node::BitXor(left, right) => Node::fmt_binary(f, "^", precedence, left, right),
+            Node::ShiftLeft(left, right) => Node::fmt_binary(f, "<<", precedence, left, right),
+            Node::ShiftRight(left, right) => Node::fmt_binary(f, ">>", precedence, left, right),
+            // `**` is right associative and takes a unary right operand.
+            Node::Power(left, right) => {
+                left.fmt_operand(f, precedence + 1)?;
+                write!(f, " ** ")?;
+                right.fmt_operand(f, precedence - 1)
+            }
+            Node::Call { callee, args } => {
+                callee.fmt_operand(f, precedence)?;
+                write!(f, "(")?;
+                fmt_list(f, args)?;
+                write!(f, ")")
+            }
+            Node::Index { target, index } => {
+                target.fmt_operand(f, precedence)?;
+                write!(f, "[{index}]")
+            }
+            Node::Member { target, field } => {
+                target.fmt_operand(f, precedence)?;
+                write!(f, ".{field}")
+            }
+            Node::Array(elements) => {
+                write!(f, "[")?;
+                fmt_list(f, elements)?;
+                write!(f, "]")
+            }
+            Node::Tuple(elements) => {
+                write!(f, "(")?;
+                fmt_list(f, elements)?;
+                write!(f, ")")
+            }
+            // `{}` is an empty block, so an empty map is `{:}`.
+            Node::Map(entries) if entries.is_empty() => write!(f, "{{:}}"),
+            Node::Map(entries) => {
+                write!(f, "{{")?;
+                for (idx, (key, value)) in entries.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{key}: {value}")?;
+                }
+                write!(f, "}}")
+            }
+            Node::Return(value) => write!(f, "return {value};"),
+            Node::Block(statements) => {
+                write!(f, "{{")?;
+                for statement in statements {
+                    write!(f, " ")?;
+                    statement.fmt_statement(f)?;
+                }
+                write!(f, " }}")
+            }
+            Node::BlockExpression { statements, value } => {
+                write!(f, "{{")?;
+                for statement in statements {
+                    write!(f, " ")?;
+                    statement.fmt_statement(f)?;
+                }
+                write!(f, " {value} }}")
+            }
+            Node::If(node) => write!(f, "{node}"),
+            Node::Function(node) => write!(f, "{node}"),
+            Node::Lambda { parameters, body } => {
+                write!(f, "function(")?;
+                fmt_list(f, parameters)?;
+                write!(f, ") {body}")
+            }
+            Node::Var(name, value) => write!(f, "var {name} = {value};"),
+            Node::Destructure { pattern, value } => {
+                write!(f, "var (")?;
+                fmt_list(f, pattern)?;
+                write!(f, ") = {value};")
+            }
+            Node::Assignment(name, value) => write!(f, "{name} = {value};"),
+            Node::While(node) => write!(f, "{node}"),
+            Node::Error => write!(f, "<error>"),
+        }
+    }
+}
+
+impl Display for If {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "if ({}) ", self.condition)?;
+        if *self.alternative == Node::Block(vec![]) {
+            return self.consequence.fmt_statement(f);
+        }
+
+        self.consequence.fmt_closed(f)?;
+        write!(f, " else ")?;
+        self.alternative.fmt_statement(f)
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "function {}(", self.name)?;
+        fmt_list(f, &self.parameters)?;
+        write!(f, ") {}", self.body)
+    }
+}
+
+impl Display for While {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "while ({}) ", self.condition)?;
+        let Some(alternative) = &self.alternative else {
+            return self.body.fmt_statement(f);
+        };
+
+        self.body.fmt_closed(f)?;
+        write!(f, " else {alternative}")
+    }
 }
@@ -0,0 +1,271 @@
+#[cfg(test)]
+mod tests;
+
+use std::fmt::Write;
+
+use super::{Function, If, Node, StringPart, While};
+
+pub fn to_json(node: &Node) -> String {
+    let mut out = String::new();
+    write_node(&mut out, node);
+    out
+}
+
+fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if ch.is_control() => {
+                let _ = write!(out, "\\u{:04x}", ch as u32);
+            }
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+}
+
+fn write_field(out: &mut String, name: &str) {
+    out.push(',');
+    write_string(out, name);
+    out.push(':');
+}
+
+fn write_array<T>(out: &mut String, items: &[T], write_item: impl Fn(&mut String, &T)) {
+    out.push('[');
+    for (idx, item) in items.iter().enumerate() {
+        if idx > 0 {
+            out.push(',');
+        }
+        write_item(out, item);
+    }
+    out.push(']');
+}
+
+fn write_names(out: &mut String, names: &[String]) {
+    write_array(out, names, |out, name| write_string(out, name));
+}
+
+fn write_nodes(out: &mut String, nodes: &[Node]) {
+    write_array(out, nodes, write_node);
+}
+
+fn write_node(out: &mut String, node: &Node) {
+    let kind = match node {
+        Node::Number(_) => "Number",
+        #[cfg(feature = "bigint")]
+        Node::BigNumber(_) => "BigNumber",
+        Node::TypedNumber { .. } => "TypedNumber",
+        Node::Char(_) => "Char",
+        Node::String(_) => "String",
+        Node::Interpolation(_) => "Interpolation",
+        Node::Id(_) => "Id",
+        Node::Not(_) => "Not",
+        Node::Negate(_) => "Negate",
+        Node::Equal(..) => "Equal",
+        Node::NotEqual(..) => "NotEqual",
+        Node::Add(..) => "Add",
+        Node::Subtract(..) => "Subtract",
+        Node::Multiply(..) => "Multiply",
+        Node::Divide(..) => "Divide",
+        Node::IntDivide(..) => "IntDivide",
+        Node::Modulo(..) => "Modulo",
+        Node::BitNot(_) => "BitNot",
+        Node::BitAnd(..) => "BitAnd",
+        Node::BitOr(..) => "BitOr",
+        Node::BitXor(..) => "BitXor",
+        Node::ShiftLeft(..) => "ShiftLeft",
+        Node::ShiftRight(..) => "ShiftRight",
+        Node::Power(..) => "Power",
+        Node::Call { .. } => "Call",
+        Node::Index { .. } => "Index",
+        Node::Member { .. } => "Member",
+        Node::Array(_) => "Array",
+        Node::Tuple(_) => "Tuple",
+        Node::Map(_) => "Map",
+        Node::Return(_) => "Return",
+        Node::Block(_) => "Block",
+        Node::BlockExpression { .. } => "BlockExpression",
+        Node::If(_) => "If",
+        Node::Function(_) => "Function",
+        Node::Lambda { .. } => "Lambda",
+        Node::Var(..) => "Var",
+        Node::Destructure { .. } => "Destructure",
+        Node::Assignment(..) => "Assignment",
+        Node::While(_) => "While",
+        Node::Error => "Error",
+    };
+
+    out.push_str("{\"type\":");
+    write_string(out, kind);
+
+    match node {
+        Node::Number(value) => {
+            write_field(out, "value");
+            let _ = write!(out, "{value}");
+        }
+        #[cfg(feature = "bigint")]
+        Node::BigNumber(value) => {
+            write_field(out, "value");
+            let _ = write!(out, "{value}");
+        }
+        Node::TypedNumber { value, suffix } => {
+            write_field(out, "value");
+            let _ = write!(out, "{value}");
+            write_field(out, "suffix");
+            write_string(out, suffix.suffix());
+        }
+        Node::Char(value) => {
+            write_field(out, "value");
+            write_string(out, value.encode_utf8(&mut [0; 4]));
+        }
+        Node::String(value) => {
+            write_field(out, "value");
+            write_string(out, value);
+        }
+        Node::Interpolation(parts) => {
+            write_field(out, "parts");
+            write_array(out, parts, |out, part| match part {
+                StringPart::Literal(text) => write_string(out, text),
+                StringPart::Expression(node) => write_node(out, node),
+            });
+        }
+        Node::Id(name) => {
+            write_field(out, "name");
+            write_string(out, name);
+        }
+        Node::Not(term) | Node::Negate(term) | Node::BitNot(term) => {
+            write_field(out, "term");
+            write_node(out, term);
+        }
+        Node::Equal(left, right)
+        | Node::NotEqual(left, right)
+        | Node::Add(left, right)
+        | Node::Subtract(left, right)
+        | Node::Multiply(left, right)
+        | Node::Divide(left, right)
+        | Node::IntDivide(left, right)
+        | Node::Modulo(left, right)
+        | Node::BitAnd(left, right)
+        | Node::BitOr(left, right)
+        | Node::BitXor(left, right)
+        | Node::ShiftLeft(left, right)
+        | Node::ShiftRight(left, right)
+        | Node::Power(left, right) => {
+            write_field(out, "left");
+            write_node(out, left);
+            write_field(out, "right");
+            write_node(out, right);
+        }
+        Node::Call { callee, args } => {
+            write_field(out, "callee");
+            write_node(out, callee);
+            write_field(out, "args");
+            write_nodes(out, args);
+        }
+        Node::Index { target, index } => {
+            write_field(out, "target");
+            write_node(out, target);
+            write_field(out, "index");
+            write_node(out, index);
+        }
+        Node::Member { target, field } => {
+            write_field(out, "target");
+            write_node(out, target);
+            write_field(out, "field");
+            write_string(out, field);
+        }
+        Node::Array(elements) | Node::Tuple(elements) => {
+            write_field(out, "elements");
+            write_nodes(out, elements);
+        }
+        Node::Map(entries) => {
+            write_field(out, "entries");
+            write_array(out, entries, |out, (key, value)| {
+                out.push_str("{\"key\":");
+                write_node(out, key);
+                write_field(out, "value");
+                write_node(out, value);
+                out.push('}');
+            });
+        }
+        Node::Return(value) => {
+            write_field(out, "value");
+            write_node(out, value);
+        }
+        Node::Block(statements) => {
+            write_field(out, "statements");
+            write_nodes(out, statements);
+        }
+        Node::BlockExpression { statements, value } => {
+            write_field(out, "statements");
+            write_nodes(out, statements);
+            write_field(out, "value");
+            write_node(out, value);
+        }
+        Node::If(If {
+            condition,
+            consequence,
+            alternative,
+        }) => {
+            write_field(out, "condition");
+            write_node(out, condition);
+            write_field(out, "consequence");
+            write_node(out, consequence);
+            write_field(out, "alternative");
+            write_node(out, alternative);
+        }
+        Node::Function(Function {
+            name,
+            parameters,
+            body,
+        }) => {
+            write_field(out, "name");
+            write_string(out, name);
+            write_field(out, "parameters");
+            write_names(out, parameters);
+            write_field(out, "body");
+            write_node(out, body);
+        }
+        Node::Lambda { parameters, body } => {
+            write_field(out, "parameters");
+            write_names(out, parameters);
+            write_field(out, "body");
+            write_node(out, body);
+        }
+        Node::Var(name, value) | Node::Assignment(name, value) => {
+            write_field(out, "name");
+            write_string(out, name);
+            write_field(out, "value");
+            write_node(out, value);
+        }
+        Node::Destructure { pattern, value } => {
+            write_field(out, "pattern");
+            write_names(out, pattern);
+            write_field(out, "value");
+            write_node(out, value);
+        }
+        Node::While(While {
+            condition,
+            body,
+            alternative,
+        }) => {
+            write_field(out, "condition");
+            write_node(out, condition);
+            write_field(out, "body");
+            write_node(out, body);
+            write_field(out, "alternative");
+            match alternative {
+                Some(alternative) => write_node(out, alternative),
+                None => out.push_str("null"),
+            }
+        }
+        Node::Error => (),
+    }
+
+    out.push('}');
+}
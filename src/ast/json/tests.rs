@@ -0,0 +1,58 @@
+use super::*;
+
+#[test]
+fn to_json_binary() {
+    let node = Node::Add(Box::new(Node::Number(1)), Box::new(Node::Number(2)));
+
+    assert_eq!(
+        to_json(&node),
+        r#"{"type":"Add","left":{"type":"Number","value":1},"right":{"type":"Number","value":2}}"#
+    );
+}
+
+#[test]
+fn to_json_map() {
+    let node = Node::Map(vec![(Node::String("a".to_string()), Node::Number(1))]);
+
+    assert_eq!(
+        to_json(&node),
+        r#"{"type":"Map","entries":[{"key":{"type":"String","value":"a"},"value":{"type":"Number","value":1}}]}"#
+    );
+}
+
+#[test]
+fn to_json_escapes_strings() {
+    let node = Node::String("say \"hi\"\\\n\u{1}".to_string());
+
+    assert_eq!(
+        to_json(&node),
+        r#"{"type":"String","value":"say \"hi\"\\\n\u0001"}"#
+    );
+}
+
+#[test]
+fn to_json_statements() {
+    let node = Node::While(While {
+        condition: Box::new(Node::Id("x".to_string())),
+        body: Box::new(Node::Block(vec![])),
+        alternative: None,
+    });
+
+    assert_eq!(
+        to_json(&node),
+        r#"{"type":"While","condition":{"type":"Id","name":"x"},"body":{"type":"Block","statements":[]},"alternative":null}"#
+    );
+}
+
+#[test]
+fn to_json_interpolation() {
+    let node = Node::Interpolation(vec![
+        StringPart::Literal("hi ".to_string()),
+        StringPart::Expression(Node::Id("name".to_string())),
+    ]);
+
+    assert_eq!(
+        to_json(&node),
+        r#"{"type":"Interpolation","parts":["hi ",{"type":"Id","name":"name"}]}"#
+    );
+}
@@ -0,0 +1,323 @@
+#[cfg(test)]
+mod tests;
+
+use super::{Function, If, Node, While};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrintOptions {
+    /// Column limit past which calls, arrays and maps wrap their elements
+    /// onto separate lines. Other expressions never wrap, so lines may still
+    /// exceed it.
+    pub max_width: usize,
+    /// Number of spaces per nesting level.
+    pub indent: usize,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        Self {
+            max_width: 80,
+            indent: 4,
+        }
+    }
+}
+
+/// Renders `node` as source code, with every statement of a block on its own
+/// line. A top-level block is taken to be a program, so its statements are
+/// printed without braces. Parsing the output gives back `node`.
+pub fn to_source(node: &Node, options: &PrintOptions) -> String {
+    let printer = Printer { options };
+    if let Node::Block(statements) = node {
+        statements
+            .iter()
+            .map(|statement| printer.item(statement, 0))
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else if node.is_statement() {
+        printer.statement(node, 0)
+    } else {
+        printer.expression(node, 0, 0)
+    }
+}
+
+struct Printer<'a> {
+    options: &'a PrintOptions,
+}
+
+impl Printer<'_> {
+    fn indent(&self, level: usize) -> String {
+        " ".repeat(level * self.options.indent)
+    }
+
+    // A statement of a block. A block with a value takes a `;`, as at the end
+    // of a block it would otherwise be that block's value.
+    fn item(&self, node: &Node, level: usize) -> String {
+        let out = self.statement(node, level);
+        match node {
+            Node::BlockExpression { .. } => out + ";",
+            _ => out,
+        }
+    }
+
+    fn statement(&self, node: &Node, level: usize) -> String {
+        match node {
+            Node::Block(statements) if statements.is_empty() => "{ }".to_string(),
+            Node::Block(statements) => {
+                let mut out = "{\n".to_string();
+                for statement in statements {
+                    out.push_str(&self.indent(level + 1));
+                    out.push_str(&self.item(statement, level + 1));
+                    out.push('\n');
+                }
+                out.push_str(&self.indent(level));
+                out.push('}');
+                out
+            }
+            Node::BlockExpression { statements, value } => {
+                let mut out = "{\n".to_string();
+                for statement in statements {
+                    out.push_str(&self.indent(level + 1));
+                    out.push_str(&self.item(statement, level + 1));
+                    out.push('\n');
+                }
+                let indent = self.indent(level + 1);
+                out.push_str(&indent);
+                out.push_str(&self.expression(value, level + 1, indent.len()));
+                out.push('\n');
+                out.push_str(&self.indent(level));
+                out.push('}');
+                out
+            }
+            Node::If(If {
+                condition,
+                consequence,
+                alternative,
+            }) => {
+                let mut out = self.header("if", condition, level);
+                if **alternative == Node::Block(vec![]) {
+                    out.push_str(&self.statement(consequence, level));
+                } else {
+                    out.push_str(&self.closed(consequence, level));
+                    out.push_str(" else ");
+                    out.push_str(&self.statement(alternative, level));
+                }
+                out
+            }
+            Node::While(While {
+                condition,
+                body,
+                alternative,
+            }) => {
+                let mut out = self.header("while", condition, level);
+                match alternative {
+                    None => out.push_str(&self.statement(body, level)),
+                    Some(alternative) => {
+                        out.push_str(&self.closed(body, level));
+                        out.push_str(" else ");
+                        out.push_str(&self.statement(alternative, level));
+                    }
+                }
+                out
+            }
+            Node::Function(Function {
+                name,
+                parameters,
+                body,
+            }) => format!(
+                "function {name}({}) {}",
+                parameters.join(", "),
+                self.statement(body, level)
+            ),
+            Node::Return(value) => self.prefixed("return ", value, level),
+            Node::Var(name, value) => self.prefixed(&format!("var {name} = "), value, level),
+            Node::Destructure { pattern, value } => {
+                self.prefixed(&format!("var ({}) = ", pattern.join(", ")), value, level)
+            }
+            Node::Assignment(name, value) => self.prefixed(&format!("{name} = "), value, level),
+            Node::Error => node.to_string(),
+            expression => self.prefixed("", expression, level),
+        }
+    }
+
+    // Same as `Node::fmt_closed`: spells out empty alternatives of nested
+    // statements, so that an `else` that follows can't bind to them.
+    fn closed(&self, node: &Node, level: usize) -> String {
+        match node {
+            Node::If(If {
+                condition,
+                consequence,
+                alternative,
+            }) => format!(
+                "{}{} else {}",
+                self.header("if", condition, level),
+                self.closed(consequence, level),
+                self.closed(alternative, level)
+            ),
+            Node::While(While {
+                condition,
+                body,
+                alternative,
+            }) => format!(
+                "{}{} else {}",
+                self.header("while", condition, level),
+                self.closed(body, level),
+                alternative
+                    .as_ref()
+                    .map_or("{ }".to_string(), |alternative| self
+                        .statement(alternative, level))
+            ),
+            _ => self.statement(node, level),
+        }
+    }
+
+    fn header(&self, keyword: &str, condition: &Node, level: usize) -> String {
+        let column = level * self.options.indent + keyword.len() + 2;
+        format!("{keyword} ({}) ", self.expression(condition, level, column))
+    }
+
+    // An expression statement, or one that takes an expression after `prefix`.
+    fn prefixed(&self, prefix: &str, value: &Node, level: usize) -> String {
+        let column = level * self.options.indent + prefix.len();
+        format!("{prefix}{};", self.expression(value, level, column))
+    }
+
+    // `column` is where the expression starts, on a line indented by `level`.
+    fn expression(&self, node: &Node, level: usize, column: usize) -> String {
+        let flat = node.to_string();
+        if column + flat.chars().count() <= self.options.max_width {
+            return flat;
+        }
+
+        let precedence = node.precedence();
+        if let Some((operator, left, right)) = binary(node) {
+            let left = self.operand(left, precedence, level, column);
+            let column = end_column(&left, column) + operator.len() + 2;
+            let right = self.operand(right, precedence + 1, level, column);
+            return format!("{left} {operator} {right}");
+        }
+
+        match node {
+            Node::Not(term) | Node::Negate(term) | Node::BitNot(term) => {
+                let operator = match node {
+                    Node::Not(_) => "!",
+                    Node::Negate(_) => "-",
+                    _ => "~",
+                };
+                format!(
+                    "{operator}{}",
+                    self.operand(term, precedence, level, column + 1)
+                )
+            }
+            // `**` is right associative and takes a unary right operand.
+            Node::Power(left, right) => {
+                let left = self.operand(left, precedence + 1, level, column);
+                let column = end_column(&left, column) + 4;
+                let right = self.operand(right, precedence - 1, level, column);
+                format!("{left} ** {right}")
+            }
+            Node::Call { callee, args } => {
+                let callee = self.operand(callee, precedence, level, column);
+                let args = self.list(args, level, |arg, column| {
+                    self.expression(arg, level + 1, column)
+                });
+                format!("{callee}({args})")
+            }
+            Node::Index { target, index } => {
+                let target = self.operand(target, precedence, level, column);
+                let column = end_column(&target, column) + 1;
+                format!("{target}[{}]", self.expression(index, level, column))
+            }
+            Node::Member { target, field } => {
+                format!(
+                    "{}.{field}",
+                    self.operand(target, precedence, level, column)
+                )
+            }
+            Node::Array(elements) => {
+                let elements = self.list(elements, level, |element, column| {
+                    self.expression(element, level + 1, column)
+                });
+                format!("[{elements}]")
+            }
+            Node::Tuple(elements) => {
+                let elements = self.list(elements, level, |element, column| {
+                    self.expression(element, level + 1, column)
+                });
+                format!("({elements})")
+            }
+            Node::Map(entries) if !entries.is_empty() => {
+                let entries = self.list(entries, level, |(key, value), column| {
+                    let key = self.expression(key, level + 1, column);
+                    let column = end_column(&key, column) + 2;
+                    format!("{key}: {}", self.expression(value, level + 1, column))
+                });
+                format!("{{{entries}}}")
+            }
+            Node::Lambda { parameters, body } => format!(
+                "function({}) {}",
+                parameters.join(", "),
+                self.statement(body, level)
+            ),
+            Node::Block(_) | Node::BlockExpression { .. } => self.statement(node, level),
+            _ => flat,
+        }
+    }
+
+    fn operand(&self, node: &Node, precedence: u8, level: usize, column: usize) -> String {
+        if node.precedence() < precedence {
+            format!("({})", self.expression(node, level, column + 1))
+        } else {
+            self.expression(node, level, column)
+        }
+    }
+
+    // Puts every item on its own line, one level deeper than `level`. There's
+    // no trailing separator, as the grammar doesn't allow one.
+    fn list<T>(&self, items: &[T], level: usize, item: impl Fn(&T, usize) -> String) -> String {
+        if items.is_empty() {
+            return String::new();
+        }
+
+        let column = (level + 1) * self.options.indent;
+        let mut out = "\n".to_string();
+        for (idx, value) in items.iter().enumerate() {
+            out.push_str(&self.indent(level + 1));
+            out.push_str(&item(value, column));
+            if idx + 1 < items.len() {
+                out.push(',');
+            }
+            out.push('\n');
+        }
+        out.push_str(&self.indent(level));
+        out
+    }
+}
+
+fn binary(node: &Node) -> Option<(&'static str, &Node, &Node)> {
+    let (operator, left, right) = match node {
+        Node::Equal(left, right) => ("==", left, right),
+        Node::NotEqual(left, right) => ("!=", left, right),
+        Node::Add(left, right) => ("+", left, right),
+        Node::Subtract(left, right) => ("-", left, right),
+        Node::Multiply(left, right) => ("*", left, right),
+        Node::Divide(left, right) => ("/", left, right),
+        Node::IntDivide(left, right) => ("//", left, right),
+        Node::Modulo(left, right) => ("%", left, right),
+        Node::BitAnd(left, right) => ("&", left, right),
+        Node::BitOr(left, right) => ("|", left, right),
+        Node::BitXor(left, right) => ("^", left, right),
+        Node::ShiftLeft(left, right) => ("<<", left, right),
+        Node::ShiftRight(left, right) => (">>", left, right),
+        _ => return None,
+    };
+
+    Some((operator, left, right))
+}
+
+// Column right after `text`, which started at `column`.
+fn end_column(text: &str, column: usize) -> usize {
+    match text.rsplit_once('\n') {
+        Some((_, last)) => last.chars().count(),
+        None => column + text.chars().count(),
+    }
+}
@@ -0,0 +1,76 @@
+use super::*;
+use crate::parser::parse_program;
+
+fn options(max_width: usize) -> PrintOptions {
+    PrintOptions {
+        max_width,
+        indent: 4,
+    }
+}
+
+fn round_trip(source: &str, max_width: usize) -> String {
+    let program = parse_program(source).unwrap();
+    let printed = to_source(&program, &options(max_width));
+    assert_eq!(parse_program(&printed).unwrap(), program, "{printed}");
+    printed
+}
+
+#[test]
+fn short_call_stays_on_one_line() {
+    let printed = round_trip("f(alpha, beta);", 80);
+    assert_eq!(printed, "f(alpha, beta);");
+}
+
+#[test]
+fn long_call_wraps_arguments() {
+    let printed = round_trip("f(alpha, beta, gamma);", 16);
+    assert_eq!(printed, "f(\n    alpha,\n    beta,\n    gamma\n);");
+}
+
+#[test]
+fn long_array_wraps_elements() {
+    let printed = round_trip("var xs = [1, 2, 3];", 16);
+    assert_eq!(printed, "var xs = [\n    1,\n    2,\n    3\n];");
+}
+
+#[test]
+fn wraps_only_what_exceeds_width() {
+    let printed = round_trip("f(g(1, 2), long_name + other_name);", 24);
+    assert_eq!(printed, "f(\n    g(1, 2),\n    long_name + other_name\n);");
+}
+
+#[test]
+fn nested_statements() {
+    let printed = round_trip(
+        "function f(x) { if (x) { return [x, x, x]; } else { return x; } }",
+        80,
+    );
+    assert_eq!(
+        printed,
+        "function f(x) {\n    if (x) {\n        return [x, x, x];\n    } else {\n        return x;\n    }\n}"
+    );
+}
+
+#[test]
+fn block_value() {
+    let printed = round_trip("var x = { f(); 1 }; var y = { f(); 1; };", 10);
+    assert_eq!(
+        printed,
+        "var x = {\n    f();\n    1\n};\nvar y = {\n    f();\n    1;\n};"
+    );
+}
+
+#[test]
+fn dangling_else() {
+    round_trip("if (a) if (b) x; else y;", 80);
+    round_trip("if (a) { if (b) x; } else y;", 80);
+    round_trip("while (a) if (b) x; else y;", 80);
+}
+
+#[test]
+fn round_trip_with_narrow_width() {
+    round_trip(
+        "var m = {first: f(1, 2, 3), second: [4, 5]}; g(function(x) { return x + 1; }, (a + b) * c, !h(d, e));",
+        10,
+    );
+}
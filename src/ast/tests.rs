@@ -0,0 +1,423 @@
+use crate::parser::{parse_expression, parse_program};
+use crate::ranges::IntWidth;
+
+use super::*;
+
+fn num(value: i64) -> Box<Node> {
+    Box::new(Node::Number(value))
+}
+
+#[test]
+fn display_binary() {
+    assert_eq!(format!("{}", Node::Add(num(1), num(2))), "1 + 2");
+}
+
+#[test]
+fn display_minimal_parentheses() {
+    assert_eq!(
+        format!(
+            "{}",
+            Node::Multiply(Box::new(Node::Add(num(1), num(2))), num(3))
+        ),
+        "(1 + 2) * 3"
+    );
+    assert_eq!(
+        format!(
+            "{}",
+            Node::Add(num(1), Box::new(Node::Multiply(num(2), num(3))))
+        ),
+        "1 + 2 * 3"
+    );
+    assert_eq!(
+        format!(
+            "{}",
+            Node::Subtract(Box::new(Node::Subtract(num(8), num(4))), num(2))
+        ),
+        "8 - 4 - 2"
+    );
+    assert_eq!(
+        format!(
+            "{}",
+            Node::Subtract(num(8), Box::new(Node::Subtract(num(4), num(2))))
+        ),
+        "8 - (4 - 2)"
+    );
+    assert_eq!(
+        format!("{}", Node::Not(Box::new(Node::Equal(num(1), num(2))))),
+        "!(1 == 2)"
+    );
+}
+
+#[test]
+fn display_power() {
+    for source in [
+        "2 ** 3 ** 2",
+        "(2 ** 3) ** 2",
+        "!2 ** ~x",
+        "(!2) ** 2",
+        "f(1) ** 2",
+    ] {
+        let node = parse_expression(source).unwrap();
+        assert_eq!(node.to_string(), source);
+    }
+}
+
+#[test]
+fn display_expressions() {
+    assert_eq!(
+        format!(
+            "{}",
+            Node::Call {
+                callee: Box::new(Node::Id("f".to_string())),
+                args: vec![
+                    Node::Number(1),
+                    Node::Array(vec![Node::Id("x".to_string())])
+                ],
+            }
+        ),
+        "f(1, [x])"
+    );
+}
+
+#[test]
+fn display_char() {
+    assert_eq!(format!("{}", Node::Char('a')), "'a'");
+    assert_eq!(format!("{}", Node::Char('\n')), "'\\n'");
+    assert_eq!(format!("{}", Node::Char('\'')), "'\\''");
+    assert_eq!(format!("{}", Node::Char('\x1b')), "'\\x1b'");
+}
+
+#[test]
+fn display_string() {
+    assert_eq!(escape_string("a\tb\\c\x01"), "a\\tb\\\\c\\x01");
+    assert_eq!(
+        format!("{}", Node::String("say \"hi\"\n".to_string())),
+        "\"say \\\"hi\\\"\\n\""
+    );
+}
+
+#[test]
+fn display_string_round_trip() {
+    let node = parse_expression(r#"print("line\n\"quoted\" 'x' \\")"#).unwrap();
+    assert_eq!(parse_expression(&node.to_string()), Ok(node));
+}
+
+#[test]
+fn display_interpolation() {
+    let node = parse_expression(r#""a${x + 1}$${"\${}"}\${b}""#).unwrap();
+    assert_eq!(node.to_string(), r#""a${x + 1}$${"\${}"}\${b}""#);
+    assert_eq!(parse_expression(&node.to_string()), Ok(node));
+}
+
+#[test]
+fn structural_keys() {
+    let mut set = std::collections::HashSet::new();
+    set.insert(parse_expression("a * (b + 1)").unwrap());
+    set.insert(parse_expression("a*(b+1)").unwrap());
+    assert_eq!(set.len(), 1);
+
+    let mut nodes = vec![
+        parse_expression("b").unwrap(),
+        parse_expression("a").unwrap(),
+        parse_expression("2").unwrap(),
+    ];
+    nodes.sort();
+    assert_eq!(
+        nodes,
+        [
+            Node::Number(2),
+            Node::Id("a".to_string()),
+            Node::Id("b".to_string())
+        ]
+    );
+}
+
+#[test]
+fn display_map() {
+    for source in [
+        "a.b(1).c",
+        "(a + b).c",
+        "{:}",
+        r#"{"a": 1, f(x): {:}}"#,
+        r#"{"a": 1}["a"]"#,
+    ] {
+        let node = parse_expression(source).unwrap();
+        assert_eq!(node.to_string(), source);
+    }
+}
+
+#[test]
+fn display_lambda() {
+    let node = parse_expression("(function(a, b) { return a; })(1, 2)(3)").unwrap();
+    assert_eq!(node.to_string(), "function(a, b) { return a; }(1, 2)(3)");
+    assert_eq!(parse_expression(&node.to_string()), Ok(node));
+}
+
+#[test]
+fn display_block_value() {
+    for source in ["{ f(); 1 }", "{ f(); 1; }"] {
+        let node = parse_expression(source).unwrap();
+        assert_eq!(node.to_string(), source);
+    }
+}
+
+#[test]
+fn display_statements() {
+    let function = Node::Function(Function {
+        name: "f".to_string(),
+        parameters: vec!["a".to_string(), "b".to_string()],
+        body: Box::new(Node::Block(vec![
+            Node::Var("x".to_string(), num(1)),
+            Node::While(While {
+                condition: Box::new(Node::Id("a".to_string())),
+                body: Box::new(Node::Assignment("a".to_string(), num(0))),
+                alternative: None,
+            }),
+            Node::If(If {
+                condition: Box::new(Node::Id("b".to_string())),
+                consequence: Box::new(Node::Return(num(1))),
+                alternative: Box::new(Node::Block(vec![])),
+            }),
+            Node::Call {
+                callee: Box::new(Node::Id("g".to_string())),
+                args: vec![],
+            },
+        ])),
+    });
+
+    assert_eq!(
+        format!("{function}"),
+        "function f(a, b) { var x = 1; while (a) a = 0; if (b) return 1; g(); }"
+    );
+}
+
+#[test]
+fn display_while_else() {
+    let node = Node::If(If {
+        condition: Box::new(Node::Id("a".to_string())),
+        consequence: Box::new(Node::While(While {
+            condition: Box::new(Node::Id("b".to_string())),
+            body: Box::new(Node::Return(num(1))),
+            alternative: None,
+        })),
+        alternative: Box::new(Node::While(While {
+            condition: Box::new(Node::Id("c".to_string())),
+            body: Box::new(Node::Return(num(2))),
+            alternative: Some(Box::new(Node::Block(vec![Node::Return(num(3))]))),
+        })),
+    });
+
+    assert_eq!(
+        format!("{node}"),
+        "if (a) while (b) return 1; else { } else while (c) return 2; else { return 3; }"
+    );
+}
+
+#[test]
+fn display_dangling_else() {
+    let node = Node::If(If {
+        condition: Box::new(Node::Id("a".to_string())),
+        consequence: Box::new(Node::If(If {
+            condition: Box::new(Node::Id("b".to_string())),
+            consequence: Box::new(Node::Return(num(1))),
+            alternative: Box::new(Node::Block(vec![])),
+        })),
+        alternative: Box::new(Node::Return(num(2))),
+    });
+
+    assert_eq!(
+        format!("{node}"),
+        "if (a) if (b) return 1; else { } else return 2;"
+    );
+}
+
+#[test]
+fn display_round_trip() {
+    let program = parse_program(
+        "
+        function f(a) {
+            if (a == 0) return (1 + 2) * 3; else { return ~a & 1 << 2; }
+        }
+        f(8 - (4 - 2));
+        ",
+    )
+    .unwrap();
+
+    let Node::Block(statements) = &program else {
+        panic!("program is not a block");
+    };
+    let source: Vec<_> = statements
+        .iter()
+        .map(|node| {
+            if node.is_statement() {
+                node.to_string()
+            } else {
+                format!("{node};")
+            }
+        })
+        .collect();
+
+    assert_eq!(parse_program(&source.join("\n")), Ok(program));
+}
+
+// Minimal xorshift generator so the round-trip property can be checked
+// without pulling in a property testing dependency.
+struct Generator(u64);
+
+impl Generator {
+    const MAX_DEPTH: usize = 4;
+
+    fn next(&mut self, bound: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % bound
+    }
+
+    fn name(&mut self) -> String {
+        ["a", "b", "foo", "_bar1"][self.next(4) as usize].to_string()
+    }
+
+    fn expression(&mut self, depth: usize) -> Node {
+        let leaf = depth >= Self::MAX_DEPTH;
+        match self.next(if leaf { 3 } else { 26 }) {
+            0 => Node::Number(self.next(1000) as i64),
+            1 => Node::Id(self.name()),
+            2 => Node::Char(['a', '\n', '\'', '\\', '\x7f'][self.next(5) as usize]),
+            3 => Node::Not(Box::new(self.expression(depth + 1))),
+            4 => Node::BitNot(Box::new(self.expression(depth + 1))),
+            5 => Node::Call {
+                callee: Box::new(Node::Id(self.name())),
+                args: (0..self.next(3))
+                    .map(|_| self.expression(depth + 1))
+                    .collect(),
+            },
+            6 => Node::Array(
+                (0..self.next(3))
+                    .map(|_| self.expression(depth + 1))
+                    .collect(),
+            ),
+            7 => Node::Map(
+                (0..self.next(3))
+                    .map(|_| (self.expression(depth + 1), self.expression(depth + 1)))
+                    .collect(),
+            ),
+            8 => Node::Member {
+                target: Box::new(self.expression(depth + 1)),
+                field: self.name(),
+            },
+            24 => Node::Tuple(
+                (0..[0, 2, 3][self.next(3) as usize])
+                    .map(|_| self.expression(depth + 1))
+                    .collect(),
+            ),
+            23 => Node::TypedNumber {
+                value: self.next(300) as i64,
+                suffix: [IntWidth::I8, IntWidth::U8, IntWidth::U64][self.next(3) as usize],
+            },
+            operator => {
+                let left = Box::new(self.expression(depth + 1));
+                let right = Box::new(self.expression(depth + 1));
+                match operator {
+                    9 => Node::Equal(left, right),
+                    10 => Node::NotEqual(left, right),
+                    11 => Node::Add(left, right),
+                    12 => Node::Subtract(left, right),
+                    13 => Node::Multiply(left, right),
+                    14 => Node::Divide(left, right),
+                    15 => Node::BitAnd(left, right),
+                    16 => Node::BitOr(left, right),
+                    17 => Node::BitXor(left, right),
+                    18 => Node::ShiftLeft(left, right),
+                    19 => Node::ShiftRight(left, right),
+                    20 => Node::Index {
+                        target: left,
+                        index: right,
+                    },
+                    21 => Node::Modulo(left, right),
+                    22 => Node::Negate(left),
+                    _ => Node::Power(left, right),
+                }
+            }
+        }
+    }
+
+    fn block(&mut self, depth: usize) -> Node {
+        Node::Block(
+            (0..self.next(3))
+                .map(|_| self.statement(depth + 1))
+                .collect(),
+        )
+    }
+
+    fn statement(&mut self, depth: usize) -> Node {
+        let leaf = depth >= Self::MAX_DEPTH;
+        match self.next(if leaf { 4 } else { 10 }) {
+            0 => Node::Return(Box::new(self.expression(depth + 1))),
+            1 => Node::Var(self.name(), Box::new(self.expression(depth + 1))),
+            2 => Node::Assignment(self.name(), Box::new(self.expression(depth + 1))),
+            3 => self.expression(depth + 1),
+            4 => self.block(depth),
+            5 => Node::If(If {
+                condition: Box::new(self.expression(depth + 1)),
+                consequence: Box::new(self.statement(depth + 1)),
+                alternative: Box::new(match self.next(2) {
+                    0 => Node::Block(vec![]),
+                    _ => self.statement(depth + 1),
+                }),
+            }),
+            6 => Node::While(While {
+                condition: Box::new(self.expression(depth + 1)),
+                body: Box::new(self.statement(depth + 1)),
+                alternative: match self.next(2) {
+                    0 => None,
+                    _ => Some(Box::new(Node::Block(vec![self.statement(depth + 1)]))),
+                },
+            }),
+            7 => Node::Destructure {
+                pattern: (0..[0, 2, 3][self.next(3) as usize])
+                    .map(|_| self.name())
+                    .collect(),
+                value: Box::new(self.expression(depth + 1)),
+            },
+            8 => Node::BlockExpression {
+                statements: (0..self.next(3))
+                    .map(|_| self.statement(depth + 1))
+                    .collect(),
+                value: Box::new(self.expression(depth + 1)),
+            },
+            _ => Node::Function(Function {
+                name: self.name(),
+                parameters: (0..self.next(3)).map(|_| self.name()).collect(),
+                body: Box::new(self.block(depth)),
+            }),
+        }
+    }
+}
+
+#[test]
+fn display_round_trip_generated() {
+    let mut generator = Generator(0x2545_f491_4f6c_dd1d);
+
+    for _ in 0..1000 {
+        let expression = generator.expression(0);
+        let source = expression.to_string();
+        assert_eq!(
+            crate::parser::parse_expression(&source),
+            Ok(expression),
+            "{source}"
+        );
+
+        let statement = generator.statement(0);
+        let source = if statement.is_statement() {
+            statement.to_string()
+        } else {
+            format!("{statement};")
+        };
+        assert_eq!(
+            parse_program(&source),
+            Ok(Node::Block(vec![statement])),
+            "{source}"
+        );
+    }
+}
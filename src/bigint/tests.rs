@@ -0,0 +1,71 @@
+use super::*;
+
+fn big(digits: &str) -> BigInt {
+    match digits.strip_prefix('-') {
+        Some(digits) => &BigInt::from(0) - &BigInt::from_str_radix(digits, 10).unwrap(),
+        None => BigInt::from_str_radix(digits, 10).unwrap(),
+    }
+}
+
+#[test]
+fn parse_and_display() {
+    let digits = "1234567890123456789012345678901234567890";
+    assert_eq!(big(digits).to_string(), digits);
+    assert_eq!(
+        BigInt::from_str_radix("ffffffffffffffffffff", 16)
+            .unwrap()
+            .to_string(),
+        "1208925819614629174706175"
+    );
+    assert_eq!(
+        big("-1000000000000000000000").to_string(),
+        "-1000000000000000000000"
+    );
+    assert_eq!(BigInt::from(0).to_string(), "0");
+    assert_eq!(BigInt::from_str_radix("12a", 10), None);
+}
+
+#[test]
+fn i64_conversion() {
+    for value in [0, 1, -1, i64::MAX, i64::MIN] {
+        assert_eq!(BigInt::from(value).to_i64(), Some(value));
+        assert_eq!(BigInt::from(value).to_string(), value.to_string());
+    }
+    assert_eq!(big("9223372036854775808").to_i64(), None);
+    assert_eq!(big("-9223372036854775809").to_i64(), None);
+}
+
+#[test]
+fn arithmetic() {
+    let a = big("99999999999999999999999999999999999999");
+    let b = big("1");
+    assert_eq!(&a + &b, big("100000000000000000000000000000000000000"));
+    assert_eq!(&b - &a, big("-99999999999999999999999999999999999998"));
+    assert_eq!(&a - &a, BigInt::from(0));
+    assert_eq!(
+        &big("-12345678901234567890") * &big("98765432109876543210"),
+        big("-1219326311370217952237463801111263526900")
+    );
+}
+
+#[test]
+fn ordering() {
+    let mut values = vec![
+        big("100000000000000000000"),
+        big("-100000000000000000000"),
+        BigInt::from(-1),
+        BigInt::from(0),
+        BigInt::from(7),
+    ];
+    values.sort();
+    assert_eq!(
+        values,
+        [
+            big("-100000000000000000000"),
+            BigInt::from(-1),
+            BigInt::from(0),
+            BigInt::from(7),
+            big("100000000000000000000"),
+        ]
+    );
+}
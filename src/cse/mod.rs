@@ -0,0 +1,207 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
+
+use crate::ast::{Function, If, Node, While};
+
+/// Hoists pure subexpressions that a statement evaluates more than once into
+/// `var` temporaries declared right before it.
+///
+/// Only occurrences evaluated before the first call in the statement are
+/// shared, as the call could reassign the variables they read. Loop conditions
+/// are left alone, since they're evaluated again on every iteration.
+pub fn eliminate_common_subexpressions(node: Node) -> Node {
+    let mut names = HashSet::new();
+    node.collect_names(&mut names);
+
+    let mut pass = Pass { names, next: 0 };
+    pass.nested(node)
+}
+
+struct Pass {
+    // Every name in the program, which temporaries must not shadow.
+    names: HashSet<String>,
+    next: usize,
+}
+
+impl Pass {
+    // Wraps the statement in a block if it needs temporaries.
+    fn nested(&mut self, node: Node) -> Node {
+        let mut statements = self.statement(node);
+        if statements.len() == 1 {
+            statements.pop().unwrap()
+        } else {
+            Node::Block(statements)
+        }
+    }
+
+    // Returns the temporaries the statement needs, followed by the statement.
+    fn statement(&mut self, mut node: Node) -> Vec<Node> {
+        let mut temporaries = match &mut node {
+            Node::Block(statements) => {
+                *statements = std::mem::take(statements)
+                    .into_iter()
+                    .flat_map(|statement| self.statement(statement))
+                    .collect();
+                vec![]
+            }
+            Node::BlockExpression { statements, value } => {
+                *statements = std::mem::take(statements)
+                    .into_iter()
+                    .flat_map(|statement| self.statement(statement))
+                    .collect();
+                statements.extend(self.hoist(value));
+                vec![]
+            }
+            Node::If(If {
+                condition,
+                consequence,
+                alternative,
+            }) => {
+                let temporaries = self.hoist(condition);
+                self.nest(consequence);
+                self.nest(alternative);
+                temporaries
+            }
+            Node::While(While {
+                body, alternative, ..
+            }) => {
+                self.nest(body);
+                if let Some(alternative) = alternative {
+                    self.nest(alternative);
+                }
+                vec![]
+            }
+            Node::Function(Function { body, .. }) => {
+                self.nest(body);
+                vec![]
+            }
+            Node::Var(_, value)
+            | Node::Destructure { value, .. }
+            | Node::Assignment(_, value)
+            | Node::Return(value) => self.hoist(value),
+            Node::Error => vec![],
+            expression => self.hoist(expression),
+        };
+
+        temporaries.push(node);
+        temporaries
+    }
+
+    fn nest(&mut self, node: &mut Box<Node>) {
+        let statement = std::mem::replace(node.as_mut(), Node::Error);
+        **node = self.nested(statement);
+    }
+
+    // Replaces the repeated subexpressions of `expression`, largest first,
+    // until none are left.
+    fn hoist(&mut self, expression: &mut Node) -> Vec<Node> {
+        let mut temporaries = Vec::new();
+        loop {
+            let mut occurrences = HashMap::new();
+            count(expression, &mut occurrences, &mut false);
+
+            let Some(target) = occurrences
+                .into_iter()
+                .filter(|(_, (count, _))| *count > 1)
+                .max_by_key(|(node, (_, first))| (size(node), Reverse(*first)))
+                .map(|(node, _)| node.clone())
+            else {
+                return temporaries;
+            };
+
+            let name = self.temporary();
+            replace(expression, &target, &name, &mut false);
+            temporaries.push(Node::Var(name, Box::new(target)));
+        }
+    }
+
+    fn temporary(&mut self) -> String {
+        loop {
+            let name = format!("_t{}", self.next);
+            self.next += 1;
+            if !self.names.contains(&name) {
+                return name;
+            }
+        }
+    }
+}
+
+// Counts the compound pure subexpressions evaluated before the first call,
+// along with the order they're first seen in. Returns whether `node` could be
+// shared.
+//
+// `barrier` is set once a call or a block, which may run arbitrary
+// statements, has been evaluated.
+fn count<'a>(
+    node: &'a Node,
+    occurrences: &mut HashMap<&'a Node, (usize, usize)>,
+    barrier: &mut bool,
+) -> bool {
+    match node {
+        _ if *barrier => return false,
+        // The body isn't evaluated, and each evaluation creates a distinct
+        // closure.
+        Node::Lambda { .. } | Node::Error => return false,
+        Node::Block(_) | Node::BlockExpression { .. } => {
+            *barrier = true;
+            return false;
+        }
+        _ => (),
+    }
+
+    let children = node.children();
+    let mut pure = true;
+    for child in &children {
+        pure &= count(child, occurrences, barrier);
+    }
+
+    if let Node::Call { .. } = node {
+        *barrier = true;
+        return false;
+    }
+
+    if pure && !*barrier && !children.is_empty() {
+        let first = occurrences.len();
+        occurrences.entry(node).or_insert((0, first)).0 += 1;
+    }
+
+    pure
+}
+
+// Mirrors `count`, so exactly the counted occurrences are replaced.
+fn replace(node: &mut Node, target: &Node, name: &str, barrier: &mut bool) {
+    if *barrier {
+        return;
+    }
+
+    if node == target {
+        *node = Node::Id(name.to_string());
+        return;
+    }
+
+    match node {
+        Node::Lambda { .. } | Node::Error => return,
+        Node::Block(_) | Node::BlockExpression { .. } => {
+            *barrier = true;
+            return;
+        }
+        _ => (),
+    }
+
+    for child in node.children_mut() {
+        replace(child, target, name, barrier);
+    }
+
+    if let Node::Call { .. } = node {
+        *barrier = true;
+    }
+}
+
+fn size(node: &Node) -> usize {
+    1 + node.children().into_iter().map(size).sum::<usize>()
+}
@@ -0,0 +1,85 @@
+use crate::{interpreter::Interpreter, parser::parse_program};
+
+use super::*;
+
+fn optimize(source: &str) -> String {
+    let node = eliminate_common_subexpressions(parse_program(source).unwrap());
+    let Node::Block(statements) = node else {
+        panic!("program is not a block");
+    };
+
+    statements
+        .iter()
+        .map(|node| {
+            if node.is_statement() {
+                node.to_string()
+            } else {
+                format!("{node};")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[test]
+fn hoists_repeated_expression() {
+    assert_eq!(
+        optimize("x = (a * b) + (a * b);"),
+        "var _t0 = a * b; x = _t0 + _t0;"
+    );
+}
+
+#[test]
+fn hoists_largest_expression_first() {
+    assert_eq!(
+        optimize("return (a * b + 1) * (a * b + 1) - a * b * 2 - a * b;"),
+        "var _t0 = a * b + 1; var _t1 = a * b; return _t0 * _t0 - _t1 * 2 - _t1;"
+    );
+}
+
+#[test]
+fn skips_calls() {
+    assert_eq!(optimize("f(a) + f(a);"), "f(a) + f(a);");
+    assert_eq!(
+        optimize("x = (a * g()) + (a * g());"),
+        "x = a * g() + a * g();"
+    );
+    // `f` may reassign `a` before the second product.
+    assert_eq!(optimize("f(a * b) + a * b;"), "f(a * b) + a * b;");
+    assert_eq!(
+        optimize("print(a * b + a * b);"),
+        "var _t0 = a * b; print(_t0 + _t0);"
+    );
+}
+
+#[test]
+fn nested_statements() {
+    assert_eq!(
+        optimize("function f(_t0) { if (a + 1 == a + 1) return ~(a % 2) + (a % 2); }"),
+        "function f(_t0) { var _t1 = a + 1; if (_t1 == _t1) { var _t2 = a % 2; return ~_t2 + _t2; } }"
+    );
+    assert_eq!(
+        optimize("while (a + 1 != a + 1) a = a + 1;"),
+        "while (a + 1 != a + 1) a = a + 1;"
+    );
+    assert_eq!(
+        optimize("{ x = 1; (a + 1) * (a + 1) }"),
+        "{ x = 1; var _t0 = a + 1; _t0 * _t0 }"
+    );
+}
+
+#[test]
+fn preserves_results() {
+    let source = "
+        var a = 3; var b = 4;
+        function f(x) { a = a + x; return a * b; }
+        return (a * b) + f(a * b) + (a * b) + [a * b, a * b][1];
+    ";
+    let program = parse_program(source).unwrap();
+    let optimized = eliminate_common_subexpressions(program.clone());
+    assert_ne!(optimized, program);
+    assert_eq!(
+        Interpreter::new().run(&optimized),
+        Interpreter::new().run(&program)
+    );
+}
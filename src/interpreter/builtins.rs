@@ -0,0 +1,71 @@
+use std::{collections::HashMap, io::Write};
+
+use crate::ast::Node;
+
+use super::{Interpreter, RuntimeError, Value};
+
+pub(super) struct Builtin<W> {
+    pub(super) arity: usize,
+    pub(super) function: fn(&mut Interpreter<W>, &[Node]) -> Result<Value, RuntimeError>,
+}
+
+impl<W> Clone for Builtin<W> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<W> Copy for Builtin<W> {}
+
+pub(super) fn builtins<W: Write>() -> HashMap<&'static str, Builtin<W>> {
+    HashMap::from([
+        (
+            "assert",
+            Builtin {
+                arity: 1,
+                function: assert,
+            },
+        ),
+        (
+            "print",
+            Builtin {
+                arity: 1,
+                function: print,
+            },
+        ),
+        (
+            "len",
+            Builtin {
+                arity: 1,
+                function: len,
+            },
+        ),
+    ])
+}
+
+fn assert<W: Write>(
+    interpreter: &mut Interpreter<W>,
+    args: &[Node],
+) -> Result<Value, RuntimeError> {
+    if interpreter.evaluate(&args[0])?.is_truthy() {
+        Ok(Value::Number(0))
+    } else {
+        Err(RuntimeError::AssertionFailed(args[0].clone()))
+    }
+}
+
+fn print<W: Write>(interpreter: &mut Interpreter<W>, args: &[Node]) -> Result<Value, RuntimeError> {
+    let value = interpreter.evaluate(&args[0])?;
+    writeln!(interpreter.output, "{value}").map_err(|err| RuntimeError::Output(err.kind()))?;
+    Ok(Value::Number(0))
+}
+
+fn len<W: Write>(interpreter: &mut Interpreter<W>, args: &[Node]) -> Result<Value, RuntimeError> {
+    match interpreter.evaluate(&args[0])? {
+        Value::Array(values) => Ok(Value::Number(values.len() as i64)),
+        value => Err(RuntimeError::InvalidType {
+            expected: "array",
+            got: value,
+        }),
+    }
+}
@@ -0,0 +1,53 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use super::Value;
+
+// Scopes are shared, so a closure keeps the scope it was defined in alive and
+// sees later assignments to it.
+#[derive(Clone, Default)]
+pub(super) struct Environment(Rc<RefCell<Scope>>);
+
+#[derive(Default)]
+struct Scope {
+    variables: HashMap<String, Value>,
+    parent: Option<Environment>,
+}
+
+impl Environment {
+    pub(super) fn child(&self) -> Self {
+        Environment(Rc::new(RefCell::new(Scope {
+            variables: HashMap::new(),
+            parent: Some(self.clone()),
+        })))
+    }
+
+    pub(super) fn declare(&self, name: String, value: Value) {
+        self.0.borrow_mut().variables.insert(name, value);
+    }
+
+    pub(super) fn get(&self, name: &str) -> Option<Value> {
+        let scope = self.0.borrow();
+        match scope.variables.get(name) {
+            Some(value) => Some(value.clone()),
+            None => scope.parent.as_ref()?.get(name),
+        }
+    }
+
+    // Returns false if the variable isn't declared in any enclosing scope.
+    pub(super) fn assign(&self, name: &str, value: Value) -> bool {
+        let mut scope = self.0.borrow_mut();
+        if let Some(variable) = scope.variables.get_mut(name) {
+            *variable = value;
+            return true;
+        }
+
+        match &scope.parent {
+            Some(parent) => parent.assign(name, value),
+            None => false,
+        }
+    }
+
+    pub(super) fn ptr_eq(&self, other: &Environment) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
@@ -0,0 +1,734 @@
+#[cfg(test)]
+mod tests;
+
+mod builtins;
+mod environment;
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug, Display},
+    io::{self, Write},
+    rc::Rc,
+};
+
+use crate::ast::{self, Node};
+#[cfg(feature = "bigint")]
+use crate::bigint::BigInt;
+
+use builtins::Builtin;
+use environment::Environment;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(i64),
+    /// Integer outside the `i64` range.
+    #[cfg(feature = "bigint")]
+    BigNumber(BigInt),
+    /// Result of a division under `DivisionMode::Float`.
+    Float(f64),
+    Char(char),
+    String(String),
+    Array(Vec<Value>),
+    Tuple(Vec<Value>),
+    Function(Closure),
+}
+
+#[derive(Clone)]
+pub struct Closure {
+    name: Option<String>,
+    parameters: Rc<[String]>,
+    body: Rc<Node>,
+    environment: Environment,
+}
+
+impl Closure {
+    fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("<anonymous>")
+    }
+}
+
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.body, &other.body) && self.environment.ptr_eq(&other.environment)
+    }
+}
+
+impl Eq for Closure {}
+
+impl Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Closure({})", self.name())
+    }
+}
+
+impl Value {
+    fn as_number(&self) -> Result<i64, RuntimeError> {
+        match self {
+            Value::Number(value) => Ok(*value),
+            _ => Err(RuntimeError::InvalidType {
+                expected: "number",
+                got: self.clone(),
+            }),
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Number(value) => *value != 0,
+            Value::Float(value) => *value != 0.0,
+            #[cfg(feature = "bigint")]
+            Value::BigNumber(_) => true,
+            Value::Char(value) => *value != '\0',
+            Value::String(_) | Value::Array(_) | Value::Tuple(_) | Value::Function(_) => true,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(value) => write!(f, "{value}"),
+            #[cfg(feature = "bigint")]
+            Value::BigNumber(value) => write!(f, "{value}"),
+            Value::Float(value) => write!(f, "{value}"),
+            Value::Char(value) => write!(f, "{value}"),
+            Value::String(value) => write!(f, "{value}"),
+            Value::Array(values) => {
+                write!(f, "[")?;
+                fmt_values(f, values)?;
+                write!(f, "]")
+            }
+            Value::Tuple(values) => {
+                write!(f, "(")?;
+                fmt_values(f, values)?;
+                write!(f, ")")
+            }
+            Value::Function(closure) => write!(f, "<function {}>", closure.name()),
+        }
+    }
+}
+
+fn fmt_values(f: &mut fmt::Formatter<'_>, values: &[Value]) -> fmt::Result {
+    for (idx, value) in values.iter().enumerate() {
+        if idx > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{value}")?;
+    }
+
+    Ok(())
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Number(value as i64)
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl From<BigInt> for Value {
+    fn from(value: BigInt) -> Self {
+        match value.to_i64() {
+            Some(value) => Value::Number(value),
+            None => Value::BigNumber(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeError {
+    UndefinedVariable(String),
+    UndefinedFunction(String),
+    ArityMismatch {
+        callee: String,
+        expected: usize,
+        got: usize,
+    },
+    DivisionByZero,
+    InvalidShift(i64),
+    IndexOutOfBounds {
+        index: i64,
+        length: usize,
+    },
+    NegativeExponent(i64),
+    PatternMismatch {
+        expected: usize,
+        got: usize,
+    },
+    AssertionFailed(Node),
+    InvalidType {
+        expected: &'static str,
+        got: Value,
+    },
+    Output(io::ErrorKind),
+    StepLimitExceeded,
+    Unsupported(Node),
+}
+
+// Negative indices count from the end, so -1 is the last element.
+fn element(target: Value, index: i64) -> Result<Value, RuntimeError> {
+    let Value::Array(mut elements) = target else {
+        return Err(RuntimeError::InvalidType {
+            expected: "array",
+            got: target,
+        });
+    };
+
+    let length = elements.len();
+    let position = if index < 0 {
+        length.checked_sub(index.unsigned_abs() as usize)
+    } else {
+        Some(index as usize).filter(|position| *position < length)
+    };
+
+    match position {
+        Some(position) => Ok(elements.swap_remove(position)),
+        None => Err(RuntimeError::IndexOutOfBounds { index, length }),
+    }
+}
+
+fn shift_amount(amount: i64) -> Result<u32, RuntimeError> {
+    match u32::try_from(amount) {
+        Ok(amount) if amount < i64::BITS => Ok(amount),
+        _ => Err(RuntimeError::InvalidShift(amount)),
+    }
+}
+
+fn power(mut base: i64, mut exponent: i64) -> Result<i64, RuntimeError> {
+    if exponent < 0 {
+        return Err(RuntimeError::NegativeExponent(exponent));
+    }
+
+    let mut result: i64 = 1;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result.wrapping_mul(base);
+        }
+        base = base.wrapping_mul(base);
+        exponent >>= 1;
+    }
+
+    Ok(result)
+}
+
+/// Integer operations that wrap around on overflow. With the `bigint` feature,
+/// an operand that is already a big integer makes them compute with big
+/// integers instead, so `i64` arithmetic wraps either way. Other operations
+/// still require their operands to fit in an `i64`.
+#[derive(Debug, Clone, Copy)]
+enum WrappingOp {
+    Add,
+    Subtract,
+    Multiply,
+}
+
+impl WrappingOp {
+    fn apply(self, left: Value, right: Value) -> Result<Value, RuntimeError> {
+        if let Some((a, b)) = float_operands(&left, &right) {
+            return Ok(Value::Float(match self {
+                WrappingOp::Add => a + b,
+                WrappingOp::Subtract => a - b,
+                WrappingOp::Multiply => a * b,
+            }));
+        }
+
+        #[cfg(feature = "bigint")]
+        if let Some(value) = self.promote(&left, &right) {
+            return Ok(value);
+        }
+
+        let (a, b) = (left.as_number()?, right.as_number()?);
+        Ok(Value::Number(match self {
+            WrappingOp::Add => a.wrapping_add(b),
+            WrappingOp::Subtract => a.wrapping_sub(b),
+            WrappingOp::Multiply => a.wrapping_mul(b),
+        }))
+    }
+
+    #[cfg(feature = "bigint")]
+    fn promote(self, left: &Value, right: &Value) -> Option<Value> {
+        if !matches!(left, Value::BigNumber(_)) && !matches!(right, Value::BigNumber(_)) {
+            return None;
+        }
+
+        let big = |value: &Value| match value {
+            Value::Number(value) => Some(BigInt::from(*value)),
+            Value::BigNumber(value) => Some(value.clone()),
+            _ => None,
+        };
+        let (a, b) = (big(left)?, big(right)?);
+        let value = match self {
+            WrappingOp::Add => &a + &b,
+            WrappingOp::Subtract => &a - &b,
+            WrappingOp::Multiply => &a * &b,
+        };
+        Some(value.into())
+    }
+}
+
+// Operands of arithmetic on floats: a float and either another float or an
+// integer, which is converted.
+fn float_operands(left: &Value, right: &Value) -> Option<(f64, f64)> {
+    let float = |value: &Value| match value {
+        Value::Number(value) => Some(*value as f64),
+        Value::Float(value) => Some(*value),
+        _ => None,
+    };
+
+    match (left, right) {
+        (Value::Float(_), _) | (_, Value::Float(_)) => Some((float(left)?, float(right)?)),
+        _ => None,
+    }
+}
+
+/// How the remainder of a division with a negative operand is signed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArithMode {
+    /// The remainder takes the sign of the dividend, like Rust's `%`.
+    #[default]
+    Truncated,
+    /// The remainder is never negative.
+    Euclidean,
+}
+
+/// What `/` produces for two integers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DivisionMode {
+    /// An integer, truncated toward zero.
+    #[default]
+    Integer,
+    /// A float, so `7 / 2` is `3.5`.
+    Float,
+}
+
+// Wraps like the other operators, so `i64::MIN / -1` is `i64::MIN`.
+fn quotient(a: i64, b: i64) -> Result<i64, RuntimeError> {
+    if b == 0 {
+        return Err(RuntimeError::DivisionByZero);
+    }
+
+    Ok(a.wrapping_div(b))
+}
+
+fn remainder(mode: ArithMode, a: i64, b: i64) -> Result<i64, RuntimeError> {
+    if b == 0 {
+        return Err(RuntimeError::DivisionByZero);
+    }
+
+    match mode {
+        ArithMode::Truncated => Ok(a.wrapping_rem(b)),
+        ArithMode::Euclidean => Ok(a.wrapping_rem_euclid(b)),
+    }
+}
+
+enum Flow {
+    Normal,
+    Return(Value),
+}
+
+pub struct Interpreter<W = io::Stdout> {
+    builtins: HashMap<&'static str, Builtin<W>>,
+    environment: Environment,
+    output: W,
+    step_limit: Option<usize>,
+    steps: usize,
+    arith_mode: ArithMode,
+    division_mode: DivisionMode,
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::with_output(io::stdout())
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: Write> Interpreter<W> {
+    pub fn with_output(output: W) -> Self {
+        Self {
+            builtins: builtins::builtins(),
+            environment: Environment::default(),
+            output,
+            step_limit: None,
+            steps: 0,
+            arith_mode: ArithMode::default(),
+            division_mode: DivisionMode::default(),
+        }
+    }
+
+    // Every executed statement counts as a step, so a limit bounds loops that
+    // would otherwise never terminate.
+    pub fn with_step_limit(mut self, limit: Option<usize>) -> Self {
+        self.step_limit = limit;
+        self
+    }
+
+    pub fn with_arith_mode(mut self, mode: ArithMode) -> Self {
+        self.arith_mode = mode;
+        self
+    }
+
+    pub fn with_division_mode(mut self, mode: DivisionMode) -> Self {
+        self.division_mode = mode;
+        self
+    }
+
+    pub fn into_output(self) -> W {
+        self.output
+    }
+
+    pub fn run(&mut self, program: &Node) -> Result<Value, RuntimeError> {
+        self.steps = 0;
+        let previous = std::mem::take(&mut self.environment);
+        let res = self.execute(program);
+        self.environment = previous;
+
+        match res? {
+            Flow::Normal => Ok(Value::Number(0)),
+            Flow::Return(value) => Ok(value),
+        }
+    }
+
+    fn step(&mut self) -> Result<(), RuntimeError> {
+        self.steps += 1;
+        match self.step_limit {
+            Some(limit) if self.steps > limit => Err(RuntimeError::StepLimitExceeded),
+            _ => Ok(()),
+        }
+    }
+
+    fn execute(&mut self, node: &Node) -> Result<Flow, RuntimeError> {
+        self.step()?;
+        match node {
+            Node::Return(value) => Ok(Flow::Return(self.evaluate(value)?)),
+            Node::Block(statements) => {
+                for statement in statements {
+                    if let Flow::Return(value) = self.execute(statement)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+
+                Ok(Flow::Normal)
+            }
+            Node::BlockExpression { statements, value } => {
+                for statement in statements {
+                    if let Flow::Return(value) = self.execute(statement)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+
+                self.evaluate(value)?;
+                Ok(Flow::Normal)
+            }
+            Node::If(ast::If {
+                condition,
+                consequence,
+                alternative,
+            }) => {
+                if self.evaluate(condition)?.is_truthy() {
+                    self.execute(consequence)
+                } else {
+                    self.execute(alternative)
+                }
+            }
+            Node::While(ast::While {
+                condition,
+                body,
+                alternative,
+            }) => {
+                let mut ran = false;
+                while self.evaluate(condition)?.is_truthy() {
+                    ran = true;
+                    if let Flow::Return(value) = self.execute(body)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+
+                match alternative {
+                    Some(alternative) if !ran => self.execute(alternative),
+                    _ => Ok(Flow::Normal),
+                }
+            }
+            Node::Function(ast::Function {
+                name,
+                parameters,
+                body,
+            }) => {
+                let closure = self.closure(Some(name), parameters, body);
+                self.environment.declare(name.clone(), closure);
+                Ok(Flow::Normal)
+            }
+            Node::Var(name, value) => {
+                let value = self.evaluate(value)?;
+                self.environment.declare(name.clone(), value);
+                Ok(Flow::Normal)
+            }
+            Node::Destructure { pattern, value } => {
+                let values = match self.evaluate(value)? {
+                    Value::Tuple(values) => values,
+                    value => {
+                        return Err(RuntimeError::InvalidType {
+                            expected: "tuple",
+                            got: value,
+                        });
+                    }
+                };
+                if values.len() != pattern.len() {
+                    return Err(RuntimeError::PatternMismatch {
+                        expected: pattern.len(),
+                        got: values.len(),
+                    });
+                }
+
+                for (name, value) in pattern.iter().zip(values) {
+                    self.environment.declare(name.clone(), value);
+                }
+                Ok(Flow::Normal)
+            }
+            Node::Assignment(name, value) => {
+                let value = self.evaluate(value)?;
+                if !self.environment.assign(name, value) {
+                    return Err(RuntimeError::UndefinedVariable(name.clone()));
+                }
+
+                Ok(Flow::Normal)
+            }
+            _ if !node.is_statement() => {
+                self.evaluate(node)?;
+                Ok(Flow::Normal)
+            }
+            _ => Err(RuntimeError::Unsupported(node.clone())),
+        }
+    }
+
+    fn evaluate(&mut self, node: &Node) -> Result<Value, RuntimeError> {
+        match node {
+            Node::Number(value) | Node::TypedNumber { value, .. } => Ok(Value::Number(*value)),
+            #[cfg(feature = "bigint")]
+            Node::BigNumber(value) => Ok(value.clone().into()),
+            Node::Char(value) => Ok(Value::Char(*value)),
+            Node::String(value) => Ok(Value::String(value.clone())),
+            Node::Interpolation(parts) => {
+                let mut value = String::new();
+                for part in parts {
+                    match part {
+                        ast::StringPart::Literal(text) => value.push_str(text),
+                        ast::StringPart::Expression(node) => {
+                            value.push_str(&self.evaluate(node)?.to_string());
+                        }
+                    }
+                }
+                Ok(Value::String(value))
+            }
+            Node::Id(name) => self
+                .environment
+                .get(name)
+                .ok_or_else(|| RuntimeError::UndefinedVariable(name.clone())),
+            Node::Not(term) => Ok((!self.evaluate(term)?.is_truthy()).into()),
+            Node::Negate(term) => {
+                let term = self.evaluate(term)?;
+                WrappingOp::Subtract.apply(Value::Number(0), term)
+            }
+            Node::Equal(left, right) => Ok((self.evaluate(left)? == self.evaluate(right)?).into()),
+            Node::NotEqual(left, right) => {
+                Ok((self.evaluate(left)? != self.evaluate(right)?).into())
+            }
+            Node::Add(left, right) => self.add(left, right),
+            Node::Subtract(left, right) => self.wrapping(WrappingOp::Subtract, left, right),
+            Node::Multiply(left, right) => self.wrapping(WrappingOp::Multiply, left, right),
+            Node::Divide(left, right) => self.divide(left, right),
+            Node::IntDivide(left, right) => self.arithmetic(left, right, quotient),
+            Node::Modulo(left, right) => {
+                let mode = self.arith_mode;
+                self.arithmetic(left, right, |a, b| remainder(mode, a, b))
+            }
+            Node::BitNot(term) => Ok(Value::Number(!self.evaluate(term)?.as_number()?)),
+            Node::BitAnd(left, right) => self.arithmetic(left, right, |a, b| Ok(a & b)),
+            Node::BitOr(left, right) => self.arithmetic(left, right, |a, b| Ok(a | b)),
+            Node::BitXor(left, right) => self.arithmetic(left, right, |a, b| Ok(a ^ b)),
+            Node::ShiftLeft(left, right) => self.arithmetic(left, right, |a, b| {
+                shift_amount(b).map(|b| a.wrapping_shl(b))
+            }),
+            Node::ShiftRight(left, right) => self.arithmetic(left, right, |a, b| {
+                shift_amount(b).map(|b| a.wrapping_shr(b))
+            }),
+            Node::Lambda { parameters, body } => Ok(self.closure(None, parameters, body)),
+            Node::Power(left, right) => self.arithmetic(left, right, power),
+            Node::Block(statements) => self.evaluate_block(statements, None),
+            Node::BlockExpression { statements, value } => {
+                self.evaluate_block(statements, Some(value))
+            }
+            Node::Call { callee, args } => self.call(callee, args),
+            Node::Index { target, index } => {
+                let target = self.evaluate(target)?;
+                let index = self.evaluate(index)?.as_number()?;
+                element(target, index)
+            }
+            Node::Array(elements) => elements
+                .iter()
+                .map(|element| self.evaluate(element))
+                .collect::<Result<_, _>>()
+                .map(Value::Array),
+            Node::Tuple(elements) => elements
+                .iter()
+                .map(|element| self.evaluate(element))
+                .collect::<Result<_, _>>()
+                .map(Value::Tuple),
+            _ if node.is_statement() => match self.execute(node)? {
+                Flow::Normal => Ok(Value::Number(0)),
+                Flow::Return(value) => Ok(value),
+            },
+            _ => Err(RuntimeError::Unsupported(node.clone())),
+        }
+    }
+
+    // A block used as an expression evaluates to its trailing expression, or 0
+    // if it doesn't have one.
+    fn evaluate_block(
+        &mut self,
+        statements: &[Node],
+        value: Option<&Node>,
+    ) -> Result<Value, RuntimeError> {
+        for statement in statements {
+            if let Flow::Return(value) = self.execute(statement)? {
+                return Ok(value);
+            }
+        }
+
+        match value {
+            Some(value) => self.evaluate(value),
+            None => Ok(Value::Number(0)),
+        }
+    }
+
+    // Strings concatenate, but only with other strings.
+    fn add(&mut self, left: &Node, right: &Node) -> Result<Value, RuntimeError> {
+        match (self.evaluate(left)?, self.evaluate(right)?) {
+            (Value::String(mut left), Value::String(right)) => {
+                left.push_str(&right);
+                Ok(Value::String(left))
+            }
+            (Value::String(_), right) => Err(RuntimeError::InvalidType {
+                expected: "string",
+                got: right,
+            }),
+            (left, right) => WrappingOp::Add.apply(left, right),
+        }
+    }
+
+    fn wrapping(
+        &mut self,
+        op: WrappingOp,
+        left: &Node,
+        right: &Node,
+    ) -> Result<Value, RuntimeError> {
+        let left = self.evaluate(left)?;
+        let right = self.evaluate(right)?;
+        op.apply(left, right)
+    }
+
+    fn divide(&mut self, left: &Node, right: &Node) -> Result<Value, RuntimeError> {
+        let left = self.evaluate(left)?;
+        let right = self.evaluate(right)?;
+        if let Some((a, b)) = float_operands(&left, &right) {
+            if b == 0.0 {
+                return Err(RuntimeError::DivisionByZero);
+            }
+            return Ok(Value::Float(a / b));
+        }
+
+        let (a, b) = (left.as_number()?, right.as_number()?);
+        match self.division_mode {
+            DivisionMode::Float if b != 0 => Ok(Value::Float(a as f64 / b as f64)),
+            _ => quotient(a, b).map(Value::Number),
+        }
+    }
+
+    fn arithmetic(
+        &mut self,
+        left: &Node,
+        right: &Node,
+        operation: impl Fn(i64, i64) -> Result<i64, RuntimeError>,
+    ) -> Result<Value, RuntimeError> {
+        let left = self.evaluate(left)?.as_number()?;
+        let right = self.evaluate(right)?.as_number()?;
+        operation(left, right).map(Value::Number)
+    }
+
+    fn closure(&self, name: Option<&String>, parameters: &[String], body: &Node) -> Value {
+        Value::Function(Closure {
+            name: name.cloned(),
+            parameters: parameters.into(),
+            body: Rc::new(body.clone()),
+            environment: self.environment.clone(),
+        })
+    }
+
+    // Named callees that aren't bound to anything fall back to the builtins,
+    // so user definitions shadow them.
+    fn call(&mut self, callee: &Node, args: &[Node]) -> Result<Value, RuntimeError> {
+        let value = match callee {
+            Node::Id(name) => match self.environment.get(name) {
+                Some(value) => value,
+                None => return self.call_builtin(name, args),
+            },
+            _ => self.evaluate(callee)?,
+        };
+
+        match value {
+            Value::Function(closure) => self.call_closure(callee, &closure, args),
+            value => Err(RuntimeError::InvalidType {
+                expected: "function",
+                got: value,
+            }),
+        }
+    }
+
+    fn call_closure(
+        &mut self,
+        callee: &Node,
+        closure: &Closure,
+        args: &[Node],
+    ) -> Result<Value, RuntimeError> {
+        if closure.parameters.len() != args.len() {
+            return Err(RuntimeError::ArityMismatch {
+                callee: callee.to_string(),
+                expected: closure.parameters.len(),
+                got: args.len(),
+            });
+        }
+
+        let environment = closure.environment.child();
+        for (param, arg) in closure.parameters.iter().zip(args) {
+            environment.declare(param.clone(), self.evaluate(arg)?);
+        }
+
+        // The body is evaluated as a block, so a trailing expression without a
+        // semicolon is returned.
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let res = self.evaluate(&closure.body);
+        self.environment = previous;
+        res
+    }
+
+    fn call_builtin(&mut self, callee: &str, args: &[Node]) -> Result<Value, RuntimeError> {
+        let Some(builtin) = self.builtins.get(callee).copied() else {
+            return Err(RuntimeError::UndefinedFunction(callee.to_string()));
+        };
+
+        if builtin.arity != args.len() {
+            return Err(RuntimeError::ArityMismatch {
+                callee: callee.to_string(),
+                expected: builtin.arity,
+                got: args.len(),
+            });
+        }
+
+        (builtin.function)(self, args)
+    }
+}
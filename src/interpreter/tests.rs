@@ -0,0 +1,636 @@
+use crate::{
+    ast::Node,
+    parser::{CommentStyle, ParseOptions, parse_program},
+};
+
+use super::*;
+
+fn run(source: &str) -> Result<Value, RuntimeError> {
+    let program = parse_program(source).unwrap();
+    Interpreter::new().run(&program)
+}
+
+#[test]
+fn run_empty() {
+    assert_eq!(run(""), Ok(Value::Number(0)));
+}
+
+#[test]
+fn run_arithmetic() {
+    assert_eq!(run("return 1 + 2 * 3 - 8 / 4;"), Ok(Value::Number(5)));
+    assert_eq!(run("return !(1 == 2) + (3 != 3);"), Ok(Value::Number(1)));
+    assert_eq!(run("return 8 - 4 - 2 == 2;"), Ok(Value::Number(1)));
+    assert_eq!(run("return 16 / 4 / 2 == 2;"), Ok(Value::Number(1)));
+    assert_eq!(run("return 8 - 4 + 2;"), Ok(Value::Number(6)));
+    assert_eq!(run("return 2 ** 3 ** 2;"), Ok(Value::Number(512)));
+    assert_eq!(run("return -2 ** 2 - -1;"), Ok(Value::Number(-3)));
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn run_big_numbers() {
+    let big = |digits| Value::BigNumber(BigInt::from_str_radix(digits, 10).unwrap());
+    assert_eq!(
+        run("return 1234567890123456789012345678901234567890 + 1;"),
+        Ok(big("1234567890123456789012345678901234567891"))
+    );
+    assert_eq!(
+        run("return 9223372036854775808 - 1;"),
+        Ok(Value::Number(i64::MAX))
+    );
+    // Without a big operand, `i64` arithmetic wraps as it does without the
+    // feature.
+    assert_eq!(
+        run("return 9223372036854775807 + 1;"),
+        Ok(Value::Number(i64::MIN))
+    );
+    assert_eq!(
+        run("return 99999999999999999999 * 99999999999999999999 - 1;"),
+        Ok(big("9999999999999999999800000000000000000000"))
+    );
+    assert_eq!(
+        run("return 18446744073709551616 - 18446744073709551615;"),
+        Ok(Value::Number(1))
+    );
+}
+
+#[test]
+fn run_variables() {
+    assert_eq!(
+        run("var x = 1; x = x + 41; return x;"),
+        Ok(Value::Number(42))
+    );
+    assert_eq!(
+        run("x = 1;"),
+        Err(RuntimeError::UndefinedVariable("x".to_string()))
+    );
+}
+
+#[test]
+fn run_block_expression() {
+    assert_eq!(
+        run("var x = { var t = 1; t + 1 }; return x;"),
+        Ok(Value::Number(2))
+    );
+    assert_eq!(run("return { var t = 1; } + 1;"), Ok(Value::Number(1)));
+    assert_eq!(run("return { 5 } + 1;"), Ok(Value::Number(6)));
+    assert_eq!(run("return { 5; } + 1;"), Ok(Value::Number(1)));
+}
+
+#[test]
+fn run_index() {
+    assert_eq!(
+        run("var xs = [10, 20, 30]; return xs[0] + xs[2];"),
+        Ok(Value::Number(40))
+    );
+    assert_eq!(
+        run("return [10, 20, 30][0 - 1] == 30;"),
+        Ok(Value::Number(1))
+    );
+    assert_eq!(run("return [10, 20, 30][0 - 3];"), Ok(Value::Number(10)));
+    assert_eq!(
+        run("return [1][5];"),
+        Err(RuntimeError::IndexOutOfBounds {
+            index: 5,
+            length: 1
+        })
+    );
+    assert_eq!(
+        run("return [1][0 - 2];"),
+        Err(RuntimeError::IndexOutOfBounds {
+            index: -2,
+            length: 1
+        })
+    );
+    assert_eq!(
+        run("return 1[0];"),
+        Err(RuntimeError::InvalidType {
+            expected: "array",
+            got: Value::Number(1)
+        })
+    );
+}
+
+#[test]
+fn run_modulo() {
+    let program = parse_program("return (0 - 7) % 3;").unwrap();
+    assert_eq!(Interpreter::new().run(&program), Ok(Value::Number(-1)));
+    assert_eq!(
+        Interpreter::new()
+            .with_arith_mode(ArithMode::Euclidean)
+            .run(&program),
+        Ok(Value::Number(2))
+    );
+
+    assert_eq!(run("return 7 % 3 * 2;"), Ok(Value::Number(2)));
+    assert_eq!(run("return 1 % 0;"), Err(RuntimeError::DivisionByZero));
+}
+
+#[test]
+fn run_float_division() {
+    let run_float = |source| {
+        let program = parse_program(source).unwrap();
+        Interpreter::new()
+            .with_division_mode(DivisionMode::Float)
+            .run(&program)
+    };
+
+    assert_eq!(run("return 7 / 2;"), Ok(Value::Number(3)));
+    assert_eq!(run_float("return 7 / 2;"), Ok(Value::Float(3.5)));
+    assert_eq!(run_float("return -7 / 2 * 2 + 1;"), Ok(Value::Float(-6.0)));
+    assert_eq!(run_float("return 1 / 4 / 2;"), Ok(Value::Float(0.125)));
+    assert_eq!(run_float("return 7 % 2;"), Ok(Value::Number(1)));
+    assert_eq!(
+        run_float("return 1 / 0;"),
+        Err(RuntimeError::DivisionByZero)
+    );
+    assert_eq!(
+        run_float("return 1 / 2 / 0;"),
+        Err(RuntimeError::DivisionByZero)
+    );
+    assert_eq!(
+        run_float("return \"${7 / 2}\";"),
+        Ok(Value::String("3.5".to_string()))
+    );
+    // The remainder policy is independent of the division mode.
+    let program = parse_program("return (0 - 7) % 2 + 7 / 2;").unwrap();
+    assert_eq!(
+        Interpreter::new()
+            .with_division_mode(DivisionMode::Float)
+            .with_arith_mode(ArithMode::Euclidean)
+            .run(&program),
+        Ok(Value::Float(4.5))
+    );
+}
+
+#[test]
+fn run_int_divide() {
+    let options = ParseOptions {
+        comments: CommentStyle {
+            double_slash: false,
+            hash: true,
+            block: true,
+        },
+        int_division: true,
+        ..Default::default()
+    };
+    let run_with = |mode, source| {
+        let program = options.parse_program(source).unwrap();
+        Interpreter::new().with_division_mode(mode).run(&program)
+    };
+
+    for mode in [DivisionMode::Integer, DivisionMode::Float] {
+        assert_eq!(run_with(mode, "return 7 // 2 == 3;"), Ok(Value::Number(1)));
+        assert_eq!(run_with(mode, "return -7 // 2;"), Ok(Value::Number(-3)));
+        assert_eq!(
+            run_with(mode, "return 1 // 0;"),
+            Err(RuntimeError::DivisionByZero)
+        );
+        assert_eq!(
+            run_with(
+                mode,
+                "var min = 0 - 9223372036854775807 - 1; return min // (0 - 1) == min;"
+            ),
+            Ok(Value::Number(1))
+        );
+    }
+    assert_eq!(
+        run_with(DivisionMode::Float, "return 7 / 2 - 7 // 2;"),
+        Ok(Value::Float(0.5))
+    );
+}
+
+#[test]
+fn run_string_concatenation() {
+    assert_eq!(
+        run(r#"var s = "a" + "b"; return s == "ab";"#),
+        Ok(Value::Number(1))
+    );
+    assert_eq!(
+        run(r#"return "a" + 1;"#),
+        Err(RuntimeError::InvalidType {
+            expected: "string",
+            got: Value::Number(1)
+        })
+    );
+    assert_eq!(
+        run(r#"return 1 + "a";"#),
+        Err(RuntimeError::InvalidType {
+            expected: "number",
+            got: Value::String("a".to_string())
+        })
+    );
+}
+
+#[test]
+fn run_interpolation() {
+    assert_eq!(
+        run(r#"var name = "world"; var xs = [1, 'a']; return "hello ${name}, ${xs} \${name}";"#),
+        Ok(Value::String("hello world, [1, a] ${name}".to_string()))
+    );
+}
+
+#[test]
+fn run_control_flow() {
+    assert_eq!(
+        run("var i = 0; var sum = 0; while (i != 5) { i = i + 1; sum = sum + i; } return sum;"),
+        Ok(Value::Number(15))
+    );
+    assert_eq!(run("if (0) return 1; else return 2;"), Ok(Value::Number(2)));
+}
+
+#[test]
+fn run_while_else() {
+    assert_eq!(
+        run("var x = 0; while (x) x = 0; else { x = 2; } return x;"),
+        Ok(Value::Number(2))
+    );
+    assert_eq!(
+        run("var x = 3; while (x != 0) x = x - 1; else { x = 7; } return x;"),
+        Ok(Value::Number(0))
+    );
+}
+
+#[test]
+fn run_functions() {
+    assert_eq!(
+        run("
+            function factorial(n) {
+                if (n == 0) return 1;
+                return n * factorial(n - 1);
+            }
+
+            return factorial(5);
+        "),
+        Ok(Value::Number(120))
+    );
+    assert_eq!(
+        run("function f(a) { return a; } return f(1, 2);"),
+        Err(RuntimeError::ArityMismatch {
+            callee: "f".to_string(),
+            expected: 1,
+            got: 2,
+        })
+    );
+    assert_eq!(
+        run("return g();"),
+        Err(RuntimeError::UndefinedFunction("g".to_string()))
+    );
+}
+
+#[test]
+fn run_function_trailing_value() {
+    assert_eq!(run("function f() { 5 } return f();"), Ok(Value::Number(5)));
+    assert_eq!(run("function f() { 5; } return f();"), Ok(Value::Number(0)));
+    assert_eq!(
+        run("var double = function(x) { x * 2 }; return double(4);"),
+        Ok(Value::Number(8))
+    );
+    assert_eq!(
+        run("function f(x) { if (x) return 1; 2 } return f(1) * 10 + f(0);"),
+        Ok(Value::Number(12))
+    );
+}
+
+#[test]
+fn run_early_return() {
+    // The return unwinds the `if`, both loops and their blocks, but not the
+    // caller, which keeps going after the call.
+    assert_eq!(
+        run("
+            function find(target) {
+                var i = 0;
+                while (i != 10) {
+                    var j = 0;
+                    while (j != 10) {
+                        if (i * 10 + j == target) {
+                            return [i, j];
+                        }
+                        j = j + 1;
+                    }
+                    i = i + 1;
+                }
+                return 0;
+            }
+
+            var found = find(42);
+            return found[0] * 100 + found[1];
+        "),
+        Ok(Value::Number(402))
+    );
+    assert_eq!(
+        run("
+            function sign(n) {
+                if (n == 0) {
+                    return 0;
+                } else {
+                    if (n >> 63) { return -1; }
+                }
+                return 1;
+            }
+
+            return sign(0) * 100 + sign(-5) * 10 + sign(5);
+        "),
+        Ok(Value::Number(-9))
+    );
+}
+
+#[test]
+fn run_globals() {
+    assert_eq!(
+        run("
+            var scale = 10;
+            function scaled(x) { return x * scale; }
+            var offset = 2;
+            function shifted(x) { return scaled(x) + offset; }
+
+            offset = 3;
+            return shifted(4);
+        "),
+        Ok(Value::Number(43))
+    );
+    assert_eq!(
+        run("
+            var calls = 0;
+            function count() { calls = calls + 1; }
+            count();
+            count();
+            return calls;
+        "),
+        Ok(Value::Number(2))
+    );
+}
+
+#[test]
+fn run_function_argument() {
+    assert_eq!(
+        run("
+            function twice(f, x) { return f(f(x)); }
+            function double(x) { return x * 2; }
+            return twice(double, 3);
+        "),
+        Ok(Value::Number(12))
+    );
+    assert_eq!(
+        run("var f = 1; return f();"),
+        Err(RuntimeError::InvalidType {
+            expected: "function",
+            got: Value::Number(1),
+        })
+    );
+}
+
+#[test]
+fn run_closures() {
+    assert_eq!(
+        run("
+            function adder(a) {
+                function add(b) { return a + b; }
+                return add;
+            }
+            var add2 = adder(2);
+            return add2(3);
+        "),
+        Ok(Value::Number(5))
+    );
+    assert_eq!(
+        run("
+            function counter() {
+                var n = 0;
+                function next() { n = n + 1; return n; }
+                return next;
+            }
+            var c = counter();
+            c();
+            c();
+            return c();
+        "),
+        Ok(Value::Number(3))
+    );
+}
+
+#[test]
+fn run_lambdas() {
+    assert_eq!(
+        run("var inc = function(x) { return x + 1; }; return inc(41);"),
+        Ok(Value::Number(42))
+    );
+    assert_eq!(
+        run("var y = 10; return (function(x) { return x + y; })(5);"),
+        Ok(Value::Number(15))
+    );
+    assert_eq!(
+        run("function adder(a) { return function(b) { return a + b; }; } return adder(1)(2);"),
+        Ok(Value::Number(3))
+    );
+}
+
+#[test]
+fn run_chars() {
+    assert_eq!(run("return 'a';"), Ok(Value::Char('a')));
+    assert_eq!(run("return 'a' == 'a';"), Ok(Value::Number(1)));
+    assert_eq!(run("return 'a' == '\\n';"), Ok(Value::Number(0)));
+    assert_eq!(
+        run("return 'a' + 1;"),
+        Err(RuntimeError::InvalidType {
+            expected: "number",
+            got: Value::Char('a'),
+        })
+    );
+}
+
+#[test]
+fn run_strings() {
+    assert_eq!(
+        run(r#"return "a\tb";"#),
+        Ok(Value::String("a\tb".to_string()))
+    );
+    assert_eq!(run(r#"return "a" != "b";"#), Ok(Value::Number(1)));
+}
+
+#[test]
+fn run_step_limit() {
+    let program = parse_program("var x = 0; while (1) { x = x + 1; }").unwrap();
+    assert_eq!(
+        Interpreter::new().with_step_limit(Some(1000)).run(&program),
+        Err(RuntimeError::StepLimitExceeded)
+    );
+
+    let program = parse_program("var x = 0; while (x != 10) { x = x + 1; } return x;").unwrap();
+    assert_eq!(
+        Interpreter::new().with_step_limit(Some(1000)).run(&program),
+        Ok(Value::Number(10))
+    );
+}
+
+#[test]
+fn run_division_by_zero() {
+    assert_eq!(run("return 1 / 0;"), Err(RuntimeError::DivisionByZero));
+}
+
+#[test]
+fn run_division_overflow() {
+    assert_eq!(
+        run("var min = 0 - 9223372036854775807 - 1; return min / (0 - 1) == min;"),
+        Ok(Value::Number(1))
+    );
+}
+
+#[test]
+fn assert_passes() {
+    assert_eq!(run("assert(1 == 1); return 1;"), Ok(Value::Number(1)));
+}
+
+#[test]
+fn assert_fails() {
+    assert_eq!(
+        run("assert(1 == 2); return 1;"),
+        Err(RuntimeError::AssertionFailed(Node::Equal(
+            Box::new(Node::Number(1)),
+            Box::new(Node::Number(2))
+        )))
+    );
+}
+
+#[test]
+fn assert_shadowed() {
+    assert_eq!(
+        run("function assert(x) { return 7; } return assert(0);"),
+        Ok(Value::Number(7))
+    );
+}
+
+#[test]
+fn builtin_print() {
+    let program = parse_program("print(42); print([1, 2]);").unwrap();
+    let mut output = Vec::new();
+
+    assert_eq!(
+        Interpreter::with_output(&mut output).run(&program),
+        Ok(Value::Number(0))
+    );
+    assert_eq!(String::from_utf8(output).unwrap(), "42\n[1, 2]\n");
+}
+
+#[test]
+fn builtin_len() {
+    assert_eq!(run("return len([1, 2, 3]) == 3;"), Ok(Value::Number(1)));
+    assert_eq!(
+        run("return len(1);"),
+        Err(RuntimeError::InvalidType {
+            expected: "array",
+            got: Value::Number(1),
+        })
+    );
+    assert_eq!(
+        run("return len();"),
+        Err(RuntimeError::ArityMismatch {
+            callee: "len".to_string(),
+            expected: 1,
+            got: 0,
+        })
+    );
+}
+
+#[test]
+fn builtin_shadowed() {
+    assert_eq!(
+        run("function len(x) { return 7; } return len([1]);"),
+        Ok(Value::Number(7))
+    );
+}
+
+#[test]
+fn run_array_arithmetic() {
+    assert_eq!(
+        run("return [1] + 1;"),
+        Err(RuntimeError::InvalidType {
+            expected: "number",
+            got: Value::Array(vec![Value::Number(1)]),
+        })
+    );
+}
+
+#[test]
+fn output_borrowed_sink() {
+    let program = parse_program("print(1); print(2);").unwrap();
+    let mut buffer = Vec::new();
+    let output: &mut dyn std::io::Write = &mut buffer;
+
+    Interpreter::with_output(output).run(&program).unwrap();
+    assert_eq!(buffer, b"1\n2\n");
+}
+
+#[test]
+fn output_owned_sink() {
+    let program = parse_program("var x = 3; print(x); print(x * 2);").unwrap();
+    let mut interpreter = Interpreter::with_output(Vec::new());
+
+    interpreter.run(&program).unwrap();
+    assert_eq!(interpreter.into_output(), b"3\n6\n");
+}
+
+#[test]
+fn run_bitwise() {
+    assert_eq!(run("return 12 & 10;"), Ok(Value::Number(8)));
+    assert_eq!(run("return 12 | 10;"), Ok(Value::Number(14)));
+    assert_eq!(run("return 12 ^ 10;"), Ok(Value::Number(6)));
+    assert_eq!(run("return ~0;"), Ok(Value::Number(-1)));
+}
+
+#[test]
+fn run_power() {
+    assert_eq!(run("return 2 ** 3 ** 2 == 512;"), Ok(Value::Number(1)));
+    assert_eq!(run("return (2 ** 3) ** 2;"), Ok(Value::Number(64)));
+    assert_eq!(run("return 7 ** 0;"), Ok(Value::Number(1)));
+    assert_eq!(
+        run("return 2 ** (0 - 1);"),
+        Err(RuntimeError::NegativeExponent(-1))
+    );
+}
+
+#[test]
+fn run_shift() {
+    assert_eq!(run("return 1 << 4 == 16;"), Ok(Value::Number(1)));
+    assert_eq!(run("return (0 - 16) >> 2;"), Ok(Value::Number(-4)));
+    assert_eq!(run("return 1 << 64;"), Err(RuntimeError::InvalidShift(64)));
+    assert_eq!(
+        run("return 1 >> (0 - 1);"),
+        Err(RuntimeError::InvalidShift(-1))
+    );
+}
+
+#[test]
+fn run_tuple() {
+    assert_eq!(
+        run("return (1 + 1, (3));"),
+        Ok(Value::Tuple(vec![Value::Number(2), Value::Number(3)]))
+    );
+    assert_eq!(run("return ();"), Ok(Value::Tuple(vec![])));
+    assert_eq!(
+        run("return (1, (2, 3));").unwrap().to_string(),
+        "(1, (2, 3))"
+    );
+}
+
+#[test]
+fn run_destructure() {
+    assert_eq!(
+        run("var (a, b) = (1, 2); return a * 10 + b;"),
+        Ok(Value::Number(12))
+    );
+    assert_eq!(
+        run("var (a, b) = (1, 2, 3);"),
+        Err(RuntimeError::PatternMismatch {
+            expected: 2,
+            got: 3
+        })
+    );
+    assert_eq!(
+        run("var (a, b) = 1;"),
+        Err(RuntimeError::InvalidType {
+            expected: "tuple",
+            got: Value::Number(1)
+        })
+    );
+}
@@ -1,2 +1,13 @@
 pub mod ast;
+#[cfg(feature = "bigint")]
+pub mod bigint;
+pub mod cse;
+pub mod interpreter;
+pub mod lint;
 pub mod parser;
+pub mod ranges;
+pub mod rename;
+pub mod span;
+
+#[cfg(feature = "profile")]
+pub use parser::{RuleProfile, profile_report, reset_profile};
@@ -0,0 +1,343 @@
+#[cfg(test)]
+mod tests;
+
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::{self, Node, escape_identifier};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    pub message: String,
+    pub severity: Severity,
+}
+
+impl Lint {
+    fn warning(message: String) -> Self {
+        Self {
+            message,
+            severity: Severity::Warning,
+        }
+    }
+
+    fn error(message: String) -> Self {
+        Self {
+            message,
+            severity: Severity::Error,
+        }
+    }
+}
+
+pub fn lint(program: &[Node]) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    lint_scope(program.iter(), &mut lints);
+    check_unreachable(program, "the top level", &mut lints);
+    check_assignments(program.iter(), &[], &mut Vec::new(), &mut lints);
+    lints
+}
+
+// Functions whose value is used need to return on every path, otherwise the
+// call silently evaluates to 0. Not every function is called for its value,
+// so this check is opt-in rather than part of `lint`.
+pub fn check_returns(program: &[Node]) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    let mut stack: Vec<&Node> = program.iter().collect();
+
+    while let Some(node) = stack.pop() {
+        if let Node::Function(ast::Function { name, body, .. }) = node
+            && !returns_on_all_paths(body)
+        {
+            lints.push(Lint::warning(format!(
+                "function `{}` may reach the end without returning a value",
+                escape_identifier(name)
+            )));
+        }
+
+        stack.extend(node.children().into_iter().rev());
+    }
+
+    lints
+}
+
+// A body ending in an expression without a semicolon returns that value.
+pub fn returns_on_all_paths(body: &Node) -> bool {
+    matches!(body, Node::BlockExpression { .. }) || always_returns(body)
+}
+
+// The value of a block that is run as a statement is discarded, so only its
+// statements can return.
+fn always_returns(statement: &Node) -> bool {
+    match statement {
+        Node::Return(_) => true,
+        Node::Block(statements) | Node::BlockExpression { statements, .. } => {
+            statements.iter().any(always_returns)
+        }
+        Node::If(ast::If {
+            consequence,
+            alternative,
+            ..
+        }) => always_returns(consequence) && always_returns(alternative),
+        // The `else` runs exactly when the body doesn't.
+        Node::While(ast::While {
+            body,
+            alternative: Some(alternative),
+            ..
+        }) => always_returns(body) && always_returns(alternative),
+        _ => false,
+    }
+}
+
+// Variables are function scoped, so every function body is checked as a
+// separate scope and the top level program forms its own. Redeclarations are
+// only reported within the same block, so that the branches of an `if` can
+// each declare the same name.
+fn lint_scope<'a>(nodes: impl Iterator<Item = &'a Node>, lints: &mut Vec<Lint>) {
+    let mut block = Vec::new();
+    let mut declared = Vec::new();
+    let mut used = HashSet::new();
+
+    for node in nodes {
+        walk(node, &mut block, &mut declared, &mut used, lints);
+    }
+
+    for name in declared {
+        if !used.contains(name) {
+            lints.push(Lint::warning(format!(
+                "unused variable `{}`",
+                escape_identifier(name)
+            )));
+        }
+    }
+}
+
+// `block` holds the names declared in the innermost block, and `declared`
+// those of the whole function.
+fn walk<'a>(
+    node: &'a Node,
+    block: &mut Vec<&'a str>,
+    declared: &mut Vec<&'a str>,
+    used: &mut HashSet<&'a str>,
+    lints: &mut Vec<Lint>,
+) {
+    match node {
+        Node::Function(ast::Function {
+            name,
+            parameters,
+            body,
+        }) => {
+            check_parameters(parameters, &format!("`{}`", escape_identifier(name)), lints);
+            lint_scope(std::iter::once(body.as_ref()), lints);
+            check_unreachable(
+                std::slice::from_ref(body),
+                &format!("`{}`", escape_identifier(name)),
+                lints,
+            );
+            // Functions can read variables of the enclosing scopes, such as
+            // globals declared at the top level.
+            collect_uses(body, used);
+            return;
+        }
+        Node::Lambda { parameters, body } => {
+            check_parameters(parameters, "an anonymous function", lints);
+            lint_scope(std::iter::once(body.as_ref()), lints);
+            check_unreachable(std::slice::from_ref(body), "an anonymous function", lints);
+            collect_uses(body, used);
+            return;
+        }
+        Node::Var(name, _) => declare_variable(name, block, declared, lints),
+        Node::Destructure { pattern, .. } => {
+            for name in pattern {
+                declare_variable(name, block, declared, lints);
+            }
+        }
+        Node::Id(name) => {
+            used.insert(name);
+        }
+        Node::Block(_) | Node::BlockExpression { .. } => {
+            let mut block = Vec::new();
+            for child in node.children() {
+                walk(child, &mut block, declared, used, lints);
+            }
+            return;
+        }
+        // Each branch of a conditional or loop is a block of its own, even
+        // without braces.
+        Node::If(_) | Node::While(_) => {
+            for child in node.children() {
+                walk(child, &mut Vec::new(), declared, used, lints);
+            }
+            return;
+        }
+        _ => (),
+    }
+
+    for child in node.children() {
+        walk(child, block, declared, used, lints);
+    }
+}
+
+fn declare_variable<'a>(
+    name: &'a str,
+    block: &mut Vec<&'a str>,
+    declared: &mut Vec<&'a str>,
+    lints: &mut Vec<Lint>,
+) {
+    if block.contains(&name) {
+        lints.push(Lint::warning(format!(
+            "variable `{}` is already declared in this scope",
+            escape_identifier(name)
+        )));
+    } else {
+        block.push(name);
+    }
+
+    if !declared.contains(&name) {
+        declared.push(name);
+    }
+}
+
+fn collect_uses<'a>(node: &'a Node, used: &mut HashSet<&'a str>) {
+    if let Node::Id(name) = node {
+        used.insert(name);
+    }
+
+    for child in node.children() {
+        collect_uses(child, used);
+    }
+}
+
+fn check_parameters(parameters: &[String], location: &str, lints: &mut Vec<Lint>) {
+    for (idx, parameter) in parameters.iter().enumerate() {
+        if parameters[..idx].contains(parameter) {
+            lints.push(Lint::error(format!(
+                "duplicate parameter `{}` in {location}",
+                escape_identifier(parameter)
+            )));
+        }
+    }
+}
+
+fn check_unreachable(statements: &[Node], location: &str, lints: &mut Vec<Lint>) {
+    if let Some(idx) = statements
+        .iter()
+        .position(|statement| matches!(statement, Node::Return(_)))
+        && idx + 1 < statements.len()
+    {
+        lints.push(unreachable_after_return(location));
+    }
+
+    for statement in statements {
+        match statement {
+            Node::Function(_) | Node::Lambda { .. } => (),
+            Node::Block(statements) => check_unreachable(statements, location, lints),
+            // The trailing value follows the last statement, which only isn't
+            // checked above if it is the `return`.
+            Node::BlockExpression { statements, value } => {
+                if let Some(Node::Return(_)) = statements.last() {
+                    lints.push(unreachable_after_return(location));
+                }
+                check_unreachable(statements, location, lints);
+                check_unreachable(std::slice::from_ref(value), location, lints);
+            }
+            _ => {
+                for child in statement.children() {
+                    check_unreachable(std::slice::from_ref(child), location, lints);
+                }
+            }
+        }
+    }
+}
+
+fn unreachable_after_return(location: &str) -> Lint {
+    Lint::warning(format!("unreachable code after `return` in {location}"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Binding {
+    Variable,
+    Function,
+}
+
+type Scope<'a> = HashMap<&'a str, Binding>;
+
+// Assignments may only target variables. A function can still be shadowed by
+// a variable or parameter of the same name, which is then assignable.
+fn check_assignments<'a>(
+    nodes: impl Iterator<Item = &'a Node> + Clone,
+    parameters: &'a [String],
+    scopes: &mut Vec<Scope<'a>>,
+    lints: &mut Vec<Lint>,
+) {
+    let mut scope: Scope = parameters
+        .iter()
+        .map(|parameter| (parameter.as_str(), Binding::Variable))
+        .collect();
+    for node in nodes.clone() {
+        declare(node, &mut scope);
+    }
+
+    scopes.push(scope);
+    for node in nodes {
+        check_assignment(node, scopes, lints);
+    }
+    scopes.pop();
+}
+
+// Declarations are visible in their whole scope, regardless of where they
+// appear in it.
+fn declare<'a>(node: &'a Node, scope: &mut Scope<'a>) {
+    match node {
+        Node::Function(ast::Function { name, .. }) => {
+            scope.entry(name).or_insert(Binding::Function);
+            return;
+        }
+        Node::Lambda { .. } => return,
+        Node::Var(name, _) => {
+            scope.insert(name, Binding::Variable);
+        }
+        Node::Destructure { pattern, .. } => {
+            for name in pattern {
+                scope.insert(name, Binding::Variable);
+            }
+        }
+        _ => (),
+    }
+
+    for child in node.children() {
+        declare(child, scope);
+    }
+}
+
+fn check_assignment<'a>(node: &'a Node, scopes: &mut Vec<Scope<'a>>, lints: &mut Vec<Lint>) {
+    match node {
+        Node::Function(ast::Function {
+            parameters, body, ..
+        })
+        | Node::Lambda { parameters, body } => {
+            check_assignments(std::iter::once(body.as_ref()), parameters, scopes, lints);
+            return;
+        }
+        Node::Assignment(name, _)
+            if scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(name.as_str()))
+                == Some(&Binding::Function) =>
+        {
+            lints.push(Lint::error(format!(
+                "cannot assign to function `{}`",
+                escape_identifier(name)
+            )));
+        }
+        _ => (),
+    }
+
+    for child in node.children() {
+        check_assignment(child, scopes, lints);
+    }
+}
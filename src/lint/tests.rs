@@ -0,0 +1,358 @@
+use crate::{ast::Node, parser::parse_program};
+
+use super::*;
+
+fn lint_source(source: &str) -> Vec<Lint> {
+    let Node::Block(program) = parse_program(source).unwrap() else {
+        panic!("program is not a block");
+    };
+
+    lint(&program)
+}
+
+fn check_returns_source(source: &str) -> Vec<Lint> {
+    let Node::Block(program) = parse_program(source).unwrap() else {
+        panic!("program is not a block");
+    };
+
+    check_returns(&program)
+}
+
+#[test]
+fn lint_clean() {
+    assert_eq!(
+        lint_source(
+            "
+            function f(a) {
+                var x = a;
+                while (x != 0) { x = x - 1; }
+                return x;
+            }
+            "
+        ),
+        vec![]
+    );
+}
+
+#[test]
+fn lint_unused_variable() {
+    assert_eq!(
+        lint_source(
+            "
+            var top = 1;
+            function f() {
+                var x = 1;
+                x = 2;
+                return 0;
+            }
+            return top;
+            "
+        ),
+        vec![Lint {
+            message: "unused variable `x`".to_string(),
+            severity: Severity::Warning,
+        }]
+    );
+}
+
+#[test]
+fn lint_global_used_in_function() {
+    assert_eq!(
+        lint_source(
+            "
+            var counter = 0;
+            function next() {
+                counter = counter + 1;
+                return counter;
+            }
+            return next();
+            "
+        ),
+        vec![]
+    );
+}
+
+#[test]
+fn lint_escapes_names() {
+    let program = [Node::Var("a\tb`".to_string(), Box::new(Node::Number(1)))];
+
+    assert_eq!(
+        lint(&program),
+        vec![Lint {
+            message: "unused variable `a\\tb\\``".to_string(),
+            severity: Severity::Warning,
+        }]
+    );
+}
+
+#[test]
+fn lint_unused_variable_per_function() {
+    assert_eq!(
+        lint_source(
+            "
+            function f() { var x = 1; return 0; }
+            function g() { var x = 1; return x; }
+            "
+        ),
+        vec![Lint {
+            message: "unused variable `x`".to_string(),
+            severity: Severity::Warning,
+        }]
+    );
+}
+
+#[test]
+fn lint_lambda_scope() {
+    assert_eq!(
+        lint_source(
+            "
+            var x = 1;
+            var f = function() { var x = 2; return x; };
+            return f() + x;
+            "
+        ),
+        vec![]
+    );
+    assert_eq!(
+        lint_source("var f = function() { var y = 2; return 0; }; return f;"),
+        vec![Lint {
+            message: "unused variable `y`".to_string(),
+            severity: Severity::Warning,
+        }]
+    );
+}
+
+#[test]
+fn lint_duplicate_parameter() {
+    assert_eq!(
+        lint_source(
+            "
+            function f(x, y, x) { return x + y; }
+            var g = function(a, a) { return a; };
+            return g;
+            "
+        ),
+        vec![
+            Lint {
+                message: "duplicate parameter `x` in `f`".to_string(),
+                severity: Severity::Error,
+            },
+            Lint {
+                message: "duplicate parameter `a` in an anonymous function".to_string(),
+                severity: Severity::Error,
+            },
+        ]
+    );
+    assert_eq!(lint_source("function f(x, y) { return x + y; }"), vec![]);
+}
+
+#[test]
+fn lint_duplicate_variable() {
+    assert_eq!(
+        lint_source("var x = 1; var x = 2; return x;"),
+        vec![Lint {
+            message: "variable `x` is already declared in this scope".to_string(),
+            severity: Severity::Warning,
+        }]
+    );
+}
+
+#[test]
+fn lint_duplicate_variable_per_block() {
+    assert_eq!(
+        lint_source(
+            "
+            var c = 1;
+            if (c) { var x = 1; print(x); } else { var x = 2; print(x); }
+            while (c) var y = 1; else { var y = 2; print(y); }
+            "
+        ),
+        vec![]
+    );
+    assert_eq!(
+        lint_source("var c = 1; if (c) { var (x, y) = (1, 2); var x = 3; print(x + y); }"),
+        vec![Lint {
+            message: "variable `x` is already declared in this scope".to_string(),
+            severity: Severity::Warning,
+        }]
+    );
+}
+
+#[test]
+fn lint_assignment_to_variable() {
+    assert_eq!(
+        lint_source(
+            "
+            var x = 1;
+            x = 2;
+            function f(f) { f = 3; return f; }
+            function g() { var h = 1; h = 2; return h; }
+            function h() { }
+            return x + f(1) + g();
+            "
+        ),
+        vec![]
+    );
+}
+
+#[test]
+fn lint_assignment_to_function() {
+    assert_eq!(
+        lint_source(
+            "
+            f = 3;
+            function f() { return 1; }
+            function g() { f = 4; var k = function() { g = 5; }; return k; }
+            return g;
+            "
+        ),
+        vec![
+            Lint {
+                message: "cannot assign to function `f`".to_string(),
+                severity: Severity::Error,
+            },
+            Lint {
+                message: "cannot assign to function `f`".to_string(),
+                severity: Severity::Error,
+            },
+            Lint {
+                message: "cannot assign to function `g`".to_string(),
+                severity: Severity::Error,
+            },
+        ]
+    );
+}
+
+#[test]
+fn lint_unreachable_after_return() {
+    assert_eq!(
+        lint_source(
+            "
+            function f(a) {
+                if (a) {
+                    return 1;
+                    a = 2;
+                }
+                return 0;
+            }
+            return f(1);
+            f(2);
+            "
+        ),
+        vec![
+            Lint {
+                message: "unreachable code after `return` in `f`".to_string(),
+                severity: Severity::Warning,
+            },
+            Lint {
+                message: "unreachable code after `return` in the top level".to_string(),
+                severity: Severity::Warning,
+            },
+        ]
+    );
+}
+
+#[test]
+fn lint_unreachable_in_lambda() {
+    assert_eq!(
+        lint_source("var f = function(x) { return 1; x; }; return f;"),
+        vec![Lint {
+            message: "unreachable code after `return` in an anonymous function".to_string(),
+            severity: Severity::Warning,
+        }]
+    );
+}
+
+#[test]
+fn returns_always() {
+    assert_eq!(
+        check_returns_source(
+            "
+            function f(a) {
+                if (a == 0) { return 1; } else if (a == 1) return 2; else { return 3; }
+            }
+            function g() { var x = 1; return x; x = 2; }
+            "
+        ),
+        vec![]
+    );
+}
+
+#[test]
+fn returns_loop_else() {
+    assert_eq!(
+        check_returns_source(
+            "
+            function f(a) {
+                while (a != 0) { return 1; } else { return 2; }
+            }
+            "
+        ),
+        vec![]
+    );
+    assert_eq!(
+        check_returns_source(
+            "
+            function g(a) {
+                while (a != 0) { a = a - 1; } else { return 2; }
+            }
+            "
+        ),
+        vec![Lint {
+            message: "function `g` may reach the end without returning a value".to_string(),
+            severity: Severity::Warning,
+        }]
+    );
+}
+
+#[test]
+fn returns_trailing_value() {
+    assert_eq!(
+        check_returns_source("function f(a) { var b = a + 1; b }"),
+        vec![]
+    );
+    // The value of a nested block is discarded.
+    assert_eq!(
+        check_returns_source("function g(a) { { a }; }"),
+        vec![Lint {
+            message: "function `g` may reach the end without returning a value".to_string(),
+            severity: Severity::Warning,
+        }]
+    );
+}
+
+#[test]
+fn returns_conditionally() {
+    assert_eq!(
+        check_returns_source(
+            "
+            function f(a) {
+                if (a == 0) return 1;
+                while (a != 0) { return 2; }
+            }
+            "
+        ),
+        vec![Lint {
+            message: "function `f` may reach the end without returning a value".to_string(),
+            severity: Severity::Warning,
+        }]
+    );
+}
+
+#[test]
+fn returns_never() {
+    assert_eq!(
+        check_returns_source(
+            "
+            function outer() {
+                function inner() { print(1); }
+                return inner();
+            }
+            "
+        ),
+        vec![Lint {
+            message: "function `inner` may reach the end without returning a value".to_string(),
+            severity: Severity::Warning,
+        }]
+    );
+}
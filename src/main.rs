@@ -1,5 +1,63 @@
-use funpiler::parser;
+use std::{env, fs, process::ExitCode};
 
-fn main() {
-    println!("Hello, world!");
+use funpiler::{
+    parser::{ParseError, parse_program, parse_resilient},
+    span::Position,
+};
+
+const USAGE: &str = "usage: funpiler [check] <file.fun>";
+
+fn main() -> ExitCode {
+    let args: Vec<_> = env::args().skip(1).collect();
+    let (check, path) = match args.as_slice() {
+        [command, path] if command == "check" => (true, path),
+        [path] => (false, path),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("{path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if check {
+        return self::check(path, &source);
+    }
+
+    match parse_program(&source) {
+        Ok(program) => {
+            println!("{program:#?}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            report(path, &source, &err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// Reports every syntax error rather than just the first one, and prints
+// nothing for a valid program.
+fn check(path: &str, source: &str) -> ExitCode {
+    let (_, errors) = parse_resilient(source);
+    for err in &errors {
+        report(path, source, err);
+    }
+
+    if errors.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn report(path: &str, source: &str, err: &ParseError) {
+    let position = Position::new(source, err.offset);
+    eprintln!("{path}:{position}: {}", err.message);
 }
@@ -1,23 +1,606 @@
+#[cfg(test)]
+mod tests;
+
 use crate::{
     ast,
-    parser::{Constant, ZeroOrMore, comma_t},
+    parser::{
+        Constant, Memoized, Nested, ZeroOrMore, assign_t, char_literal, colon_t, comma_t, dot_t,
+        else_t, function_t, id, if_t, left_brace_t, left_bracket_t, left_paren_t, minus_t, not_t,
+        number, operator_t, peek_char, profile, return_t, right_brace_t, right_bracket_t,
+        right_paren_t, semicolon_t, sep_by, separated_pair, star_star_t, string_char, tilde_t,
+        typed_number, var_t, while_t,
+    },
 };
 
-use super::{Parser, Result};
+use super::{ParseError, Parser, Result, context, ignored};
 
-fn expression(source: &str) -> Option<Result<'_, ast::Node>> {
-    todo!()
+pub(super) fn expression(source: &str) -> Option<Result<'_, ast::Node>> {
+    Nested::new(|source| binary_expression(source, 0)).parse(source)
 }
 
 fn arguments(source: &str) -> Option<Result<'_, Vec<ast::Node>>> {
-    let parser = expression
-        .bind(|arg| {
-            ZeroOrMore::new(comma_t.and(expression)).bind(move |mut args| {
-                args.insert(0, arg.clone());
-                Constant::new(args)
+    sep_by(expression, comma_t).parse(source)
+}
+
+fn array(source: &str) -> Option<Result<'_, ast::Node>> {
+    let parser = left_bracket_t
+        .and(arguments)
+        .bind(|elements| right_bracket_t.and(Constant::new(ast::Node::Array(elements))));
+
+    parser.parse(source)
+}
+
+// Integer literals too large for an `i64` only parse with the `bigint` feature.
+#[cfg(feature = "bigint")]
+fn big_number(source: &str) -> Option<Result<'_, ast::Node>> {
+    super::big_number.map(ast::Node::BigNumber).parse(source)
+}
+
+#[cfg(not(feature = "bigint"))]
+fn big_number(_: &str) -> Option<Result<'_, ast::Node>> {
+    None
+}
+
+// With a single element, the parentheses only group it.
+fn parenthesized(source: &str) -> Option<Result<'_, ast::Node>> {
+    let parser = left_paren_t.and(arguments).bind(|mut elements| {
+        let node = if elements.len() == 1 {
+            elements.pop().unwrap()
+        } else {
+            ast::Node::Tuple(elements)
+        };
+        right_paren_t.and(Constant::new(node))
+    });
+
+    parser.parse(source)
+}
+
+fn atom(source: &str) -> Option<Result<'_, ast::Node>> {
+    let parser = lambda
+        .or(id.map(|id| ast::Node::Id(id.to_string())))
+        .or(typed_number.map(|(value, suffix)| ast::Node::TypedNumber { value, suffix }))
+        .or(number.map(ast::Node::Number))
+        .or(big_number)
+        .or(char_literal.map(ast::Node::Char))
+        .or(string)
+        .or(array)
+        .or(map_or_block)
+        .or(parenthesized);
+
+    profile("atom", Memoized::new("atom", parser)).parse(source)
+}
+
+// `${` starts an embedded expression that ends at the matching `}`. Strings
+// without one are plain string literals.
+fn string(source: &str) -> Option<Result<'_, ast::Node>> {
+    let mut rest = source.strip_prefix('"')?;
+    let mut parts = Vec::new();
+    let mut text = String::new();
+
+    while !rest.starts_with('"') {
+        if let Some(embedded) = rest.strip_prefix("${") {
+            let res = ignored.and(expression).parse(embedded)?;
+            if !text.is_empty() {
+                parts.push(ast::StringPart::Literal(std::mem::take(&mut text)));
+            }
+            parts.push(ast::StringPart::Expression(res.value));
+            rest = res.source.strip_prefix('}')?;
+            continue;
+        }
+
+        let res = string_char(rest)?;
+        text.push(res.value);
+        rest = res.source;
+    }
+
+    let value = if parts.is_empty() {
+        ast::Node::String(text)
+    } else {
+        if !text.is_empty() {
+            parts.push(ast::StringPart::Literal(text));
+        }
+        ast::Node::Interpolation(parts)
+    };
+
+    ignored.value(value).parse(&rest[1..])
+}
+
+// Calls and indexing chain left to right, so `f(1)[0](2)` calls the element
+// of the array returned by `f(1)`.
+fn postfix(source: &str) -> Option<Result<'_, ast::Node>> {
+    let atom = atom(source)?;
+    Some(postfix_rest(atom.source, atom.value))
+}
+
+fn postfix_rest(mut source: &str, mut node: ast::Node) -> Result<'_, ast::Node> {
+    loop {
+        if let Some(res) = left_paren_t
+            .and(arguments)
+            .bind(|args| right_paren_t.value(args))
+            .parse(source)
+        {
+            node = ast::Node::Call {
+                callee: Box::new(node),
+                args: res.value,
+            };
+            source = res.source;
+        } else if let Some(res) = left_bracket_t
+            .and(expression)
+            .bind(|index| right_bracket_t.value(index))
+            .parse(source)
+        {
+            node = ast::Node::Index {
+                target: Box::new(node),
+                index: Box::new(res.value),
+            };
+            source = res.source;
+        } else if let Some(res) = dot_t.and(id).parse(source) {
+            // Fields are identifiers, so `a.1` is rejected; use `a[1]`.
+            node = ast::Node::Member {
+                target: Box::new(node),
+                field: res.value.to_string(),
+            };
+            source = res.source;
+        } else {
+            break;
+        }
+    }
+
+    Result {
+        source,
+        value: node,
+    }
+}
+
+// `**` binds tighter than the prefix operators and is right associative, as
+// its right operand may itself be a power.
+fn power(source: &str) -> Option<Result<'_, ast::Node>> {
+    postfix(source).map(power_rest)
+}
+
+fn power_rest(base: Result<'_, ast::Node>) -> Result<'_, ast::Node> {
+    let Some(exponent) = star_star_t.and(Nested::new(unary)).parse(base.source) else {
+        return base;
+    };
+
+    Result {
+        source: exponent.source,
+        value: ast::Node::Power(Box::new(base.value), Box::new(exponent.value)),
+    }
+}
+
+fn unary(source: &str) -> Option<Result<'_, ast::Node>> {
+    let parser = not_t
+        .and(Nested::new(unary))
+        .map(|term| ast::Node::Not(Box::new(term)))
+        .or(minus_t
+            .and(Nested::new(unary))
+            .map(|term| ast::Node::Negate(Box::new(term))))
+        .or(tilde_t
+            .and(Nested::new(unary))
+            .map(|term| ast::Node::BitNot(Box::new(term))))
+        .or(power);
+
+    parser.parse(source)
+}
+
+// Binding power of the binary operators, a higher one binding tighter.
+fn precedence(operator: &str) -> Option<u8> {
+    match operator {
+        "|" => Some(1),
+        "^" => Some(2),
+        "&" => Some(3),
+        "==" | "!=" => Some(4),
+        "<<" | ">>" => Some(5),
+        "+" | "-" => Some(6),
+        "*" | "/" | "%" => Some(7),
+        "//" if context::int_division() => Some(7),
+        _ => None,
+    }
+}
+
+fn operator(source: &str) -> Option<(Result<'_, &str>, u8)> {
+    let res = operator_t(source)?;
+    let precedence = precedence(res.value)?;
+    Some((res, precedence))
+}
+
+fn binary(operator: &str, left: ast::Node, right: ast::Node) -> ast::Node {
+    let (left, right) = (Box::new(left), Box::new(right));
+    match operator {
+        "==" => ast::Node::Equal(left, right),
+        "!=" => ast::Node::NotEqual(left, right),
+        "+" => ast::Node::Add(left, right),
+        "-" => ast::Node::Subtract(left, right),
+        "*" => ast::Node::Multiply(left, right),
+        "/" => ast::Node::Divide(left, right),
+        "//" => ast::Node::IntDivide(left, right),
+        "%" => ast::Node::Modulo(left, right),
+        "&" => ast::Node::BitAnd(left, right),
+        "|" => ast::Node::BitOr(left, right),
+        "^" => ast::Node::BitXor(left, right),
+        "<<" => ast::Node::ShiftLeft(left, right),
+        ">>" => ast::Node::ShiftRight(left, right),
+        _ => unreachable!("unknown infix operator {operator}"),
+    }
+}
+
+// Precedence climbing: the right operand only takes operators that bind
+// tighter, so operators of the same precedence fold to the left. Nesting depth
+// doesn't depend on the number of precedence levels, which keeps the stack
+// shallow for deeply parenthesised input. An operator without a right operand
+// is left unconsumed.
+fn binary_expression(source: &str, min_precedence: u8) -> Option<Result<'_, ast::Node>> {
+    let left = unary(source)?;
+    binary_rest(left.source, left.value, min_precedence)
+}
+
+fn binary_rest(
+    mut source: &str,
+    mut left: ast::Node,
+    min_precedence: u8,
+) -> Option<Result<'_, ast::Node>> {
+    // Whether `left` is a comparison folded by this loop, so not one in
+    // parentheses.
+    let mut compared = false;
+    while let Some((op, precedence)) = operator(source).filter(|(_, p)| *p >= min_precedence)
+        && let Some(right) = binary_expression(op.source, precedence + 1)
+    {
+        let comparison = matches!(op.value, "==" | "!=");
+        if comparison && compared && context::reject_chained_comparisons() {
+            context::fail(ParseError::chained_comparison(source));
+            return None;
+        }
+
+        compared = comparison;
+        left = binary(op.value, left, right.value);
+        source = right.source;
+    }
+
+    Some(Result {
+        source,
+        value: left,
+    })
+}
+
+fn return_statement(source: &str) -> Option<Result<'_, ast::Node>> {
+    let parser = return_t
+        .and(expression)
+        .bind(|expr| semicolon_t.and(Constant::new(ast::Node::Return(Box::new(expr)))));
+
+    parser.parse(source)
+}
+
+fn expression_statement(source: &str) -> Option<Result<'_, ast::Node>> {
+    let parser = expression.bind(|expr| semicolon_t.and(Constant::new(expr)));
+
+    parser.parse(source)
+}
+
+fn condition(source: &str) -> Option<Result<'_, ast::Node>> {
+    let parser = left_paren_t
+        .and(expression)
+        .bind(|expr| right_paren_t.and(Constant::new(expr)));
+
+    parser.parse(source)
+}
+
+fn if_statement(source: &str) -> Option<Result<'_, ast::Node>> {
+    let parser = if_t.and(condition).bind(|condition| {
+        statement.bind(move |consequence| {
+            let condition = condition.clone();
+            else_t.and(statement).opt().map(move |alternative| {
+                ast::Node::If(ast::If {
+                    condition: Box::new(condition.clone()),
+                    consequence: Box::new(consequence.clone()),
+                    alternative: Box::new(alternative.unwrap_or(ast::Node::Block(vec![]))),
+                })
             })
         })
-        .or(Constant::new(vec![]));
+    });
 
     parser.parse(source)
 }
+
+// The `else` of a loop is only taken when its body never ran. An empty `else`
+// block does nothing, so it is parsed the same as a missing one.
+fn while_statement(source: &str) -> Option<Result<'_, ast::Node>> {
+    let parser = while_t.and(condition).bind(|condition| {
+        statement.bind(move |body| {
+            let condition = condition.clone();
+            else_t.and(block_statement).opt().map(move |alternative| {
+                ast::Node::While(ast::While {
+                    condition: Box::new(condition.clone()),
+                    body: Box::new(body.clone()),
+                    alternative: alternative
+                        .filter(|alternative| *alternative != ast::Node::Block(vec![]))
+                        .map(Box::new),
+                })
+            })
+        })
+    });
+
+    parser.parse(source)
+}
+
+// Nothing else starts with `var` and a name, or `function` and a name, so the
+// rest of these statements is committed to.
+fn var_statement(source: &str) -> Option<Result<'_, ast::Node>> {
+    let parser = var_t.and(id).bind(|name| {
+        assign_t.cut().and(expression.cut()).bind(move |value| {
+            semicolon_t.cut().and(Constant::new(ast::Node::Var(
+                name.to_string(),
+                Box::new(value),
+            )))
+        })
+    });
+
+    parser.parse(source)
+}
+
+fn destructure_statement(source: &str) -> Option<Result<'_, ast::Node>> {
+    let parser = var_t.and(left_paren_t).and(parameters).bind(|pattern| {
+        right_paren_t
+            .cut()
+            .and(assign_t.cut())
+            .and(expression.cut())
+            .bind(move |value| {
+                semicolon_t.cut().and(Constant::new(ast::Node::Destructure {
+                    pattern: pattern.clone(),
+                    value: Box::new(value),
+                }))
+            })
+    });
+
+    parser.parse(source)
+}
+
+fn assignment_statement(source: &str) -> Option<Result<'_, ast::Node>> {
+    let parser = id.bind(|name| {
+        assign_t.and(expression).bind(move |value| {
+            semicolon_t.and(Constant::new(ast::Node::Assignment(
+                name.to_string(),
+                Box::new(value),
+            )))
+        })
+    });
+
+    parser.parse(source)
+}
+
+// What an item of a block turned out to be once parsed.
+enum Item {
+    Statement(ast::Node),
+    // An expression right before the closing `}`, which is left unconsumed.
+    Value(ast::Node),
+    // An expression right before a `:`, which is left unconsumed. Only the
+    // first item can be one, as the first key of a map.
+    Key(ast::Node),
+}
+
+fn is_block(node: &ast::Node) -> bool {
+    matches!(
+        node,
+        ast::Node::Block(_) | ast::Node::BlockExpression { .. }
+    )
+}
+
+// Continues an expression from its already parsed first atom.
+fn expression_from(source: &str, atom: ast::Node) -> Option<Result<'_, ast::Node>> {
+    let base = power_rest(postfix_rest(source, atom));
+    binary_rest(base.source, base.value, 0)
+}
+
+// Every item is parsed once, as a declaration or an expression, and what
+// follows it decides the rest: a `;` makes it a statement and the closing `}`
+// the value of the block. A block followed by anything else is a statement of
+// its own, so `{ x } - 1` is a block and a negation.
+fn block_item(source: &str) -> Option<Result<'_, Item>> {
+    if let Some(res) = declaration(source) {
+        return Some(Result {
+            source: res.source,
+            value: Item::Statement(res.value),
+        });
+    }
+
+    let expr = if peek_char(source) == Some('{') {
+        let first = atom(source)?;
+        if is_block(&first.value) && !matches!(peek_char(first.source), Some(';' | '}' | ':')) {
+            return Some(Result {
+                source: first.source,
+                value: Item::Statement(first.value),
+            });
+        }
+        expression_from(first.source, first.value)?
+    } else {
+        expression(source)?
+    };
+
+    if let Some(res) = semicolon_t.parse(expr.source) {
+        return Some(Result {
+            source: res.source,
+            value: Item::Statement(expr.value),
+        });
+    }
+
+    // A block without a value is still a statement at the end of a block.
+    let value = match (peek_char(expr.source)?, expr.value) {
+        ('}', node @ ast::Node::Block(_)) => Item::Statement(node),
+        ('}', node) => Item::Value(node),
+        (':', node) => Item::Key(node),
+        _ => return None,
+    };
+    Some(Result {
+        source: expr.source,
+        value,
+    })
+}
+
+// Parses the rest of a block, after its `{` and first `statements`, up to and
+// including the `}`. A block may end in an expression without a semicolon,
+// which is the value of the block when it's used as an expression.
+fn block_rest(mut source: &str, mut statements: Vec<ast::Node>) -> Option<Result<'_, ast::Node>> {
+    loop {
+        if let Some(res) = right_brace_t.parse(source) {
+            return Some(Result {
+                source: res.source,
+                value: ast::Node::Block(statements),
+            });
+        }
+
+        let item = Nested::new(block_item).parse(source)?;
+        source = item.source;
+        match item.value {
+            Item::Statement(node) => statements.push(node),
+            Item::Value(value) => return block_value(source, statements, value),
+            Item::Key(_) => return None,
+        }
+    }
+}
+
+fn block_value(
+    source: &str,
+    statements: Vec<ast::Node>,
+    value: ast::Node,
+) -> Option<Result<'_, ast::Node>> {
+    right_brace_t
+        .and(Constant::new(ast::Node::BlockExpression {
+            statements,
+            value: Box::new(value),
+        }))
+        .parse(source)
+}
+
+// Parses the rest of a map, after its `{` and first `key`.
+fn map_rest(source: &str, key: ast::Node) -> Option<Result<'_, ast::Node>> {
+    let value = colon_t.and(expression).parse(source)?;
+    let rest = ZeroOrMore::new(comma_t.and(separated_pair(expression, colon_t, expression)))
+        .parse(value.source)?;
+
+    let mut entries = vec![(key, value.value)];
+    entries.extend(rest.value);
+    right_brace_t
+        .and(Constant::new(ast::Node::Map(entries)))
+        .parse(rest.source)
+}
+
+// Maps and blocks both start with `{`, and their first item tells them apart:
+// an expression followed by `:` is the first key of a map. `{}` is an empty
+// block, so the empty map is written `{:}`.
+fn map_or_block(source: &str) -> Option<Result<'_, ast::Node>> {
+    let source = left_brace_t.parse(source)?.source;
+    if let Some(res) = colon_t.and(right_brace_t).parse(source) {
+        return Some(Result {
+            source: res.source,
+            value: ast::Node::Map(vec![]),
+        });
+    }
+    if peek_char(source) == Some('}') {
+        return block_rest(source, vec![]);
+    }
+
+    let first = Nested::new(block_item).parse(source)?;
+    match first.value {
+        Item::Statement(node) => block_rest(first.source, vec![node]),
+        Item::Value(value) => block_value(first.source, vec![], value),
+        Item::Key(key) => map_rest(first.source, key),
+    }
+}
+
+fn block_statement(source: &str) -> Option<Result<'_, ast::Node>> {
+    left_brace_t
+        .bind(|_| |source| block_rest(source, vec![]))
+        .parse(source)
+}
+
+fn parameters(source: &str) -> Option<Result<'_, Vec<String>>> {
+    let parser = sep_by(id, comma_t)
+        .map(|params| params.into_iter().map(str::to_string).collect::<Vec<_>>());
+
+    parser.parse(source)
+}
+
+fn lambda(source: &str) -> Option<Result<'_, ast::Node>> {
+    let parser = function_t
+        .and(left_paren_t)
+        .and(parameters)
+        .bind(|parameters| {
+            right_paren_t
+                .and(block_statement)
+                .map(move |body| ast::Node::Lambda {
+                    parameters: parameters.clone(),
+                    body: Box::new(body),
+                })
+        });
+
+    parser.parse(source)
+}
+
+fn function_statement(source: &str) -> Option<Result<'_, ast::Node>> {
+    let parser = function_t.and(id).bind(|name| {
+        left_paren_t.cut().and(parameters).bind(move |parameters| {
+            right_paren_t
+                .cut()
+                .and(block_statement.cut())
+                .map(move |body| {
+                    ast::Node::Function(ast::Function {
+                        name: name.to_string(),
+                        parameters: parameters.clone(),
+                        body: Box::new(body),
+                    })
+                })
+        })
+    });
+
+    parser.parse(source)
+}
+
+fn declaration(source: &str) -> Option<Result<'_, ast::Node>> {
+    let parser = return_statement
+        .or(if_statement)
+        .or(while_statement)
+        .or(var_statement)
+        .or(destructure_statement)
+        .or(function_statement)
+        .or(assignment_statement);
+
+    parser.parse(source)
+}
+
+// A block is a statement by itself, or with a `;` when it's written as an
+// expression statement. Anything else starting with `{` is the start of an
+// expression statement.
+fn brace_statement(source: &str) -> Option<Result<'_, ast::Node>> {
+    if peek_char(source) != Some('{') {
+        return None;
+    }
+
+    let first = atom(source)?;
+    if is_block(&first.value) {
+        let source = semicolon_t
+            .parse(first.source)
+            .map_or(first.source, |res| res.source);
+        return Some(Result {
+            source,
+            value: first.value,
+        });
+    }
+
+    let expr = expression_from(first.source, first.value)?;
+    semicolon_t
+        .and(Constant::new(expr.value))
+        .parse(expr.source)
+}
+
+pub(super) fn statement(source: &str) -> Option<Result<'_, ast::Node>> {
+    let parser = declaration.or(brace_statement).or(expression_statement);
+
+    profile("statement", Nested::new(parser)).parse(source)
+}
+
+pub(super) fn program(source: &str) -> Option<Result<'_, ast::Node>> {
+    ignored
+        .and(ZeroOrMore::new(statement))
+        .map(ast::Node::Block)
+        .parse(source)
+}
@@ -0,0 +1,1449 @@
+use crate::{
+    ast::{self, Node},
+    parser::{
+        CommentStyle, ParseError, ParseErrorKind, ParseOptions, parse_expression, parse_program,
+        parse_resilient, with_diagnostics,
+    },
+    ranges::IntWidth,
+};
+
+use super::*;
+
+fn num(value: i64) -> Box<Node> {
+    Box::new(Node::Number(value))
+}
+
+fn id(name: &str) -> Box<Node> {
+    Box::new(Node::Id(name.to_string()))
+}
+
+#[test]
+fn expression_number() {
+    assert_eq!(
+        expression.parse("42 rest"),
+        Some(Result {
+            source: "rest",
+            value: Node::Number(42),
+        })
+    );
+}
+
+#[test]
+fn expression_typed_number() {
+    let typed = |value, suffix| Node::TypedNumber { value, suffix };
+
+    assert_eq!(parse_expression("10i64"), Ok(typed(10, IntWidth::I64)));
+    assert_eq!(parse_expression("255u8"), Ok(typed(255, IntWidth::U8)));
+    assert_eq!(parse_expression("0xffu16"), Ok(typed(255, IntWidth::U16)));
+    assert_eq!(
+        parse_expression("[1i8, 2]"),
+        Ok(Node::Array(vec![typed(1, IntWidth::I8), Node::Number(2)]))
+    );
+    assert!(parse_expression("1u7").is_err());
+    assert!(parse_expression("1u8x").is_err());
+}
+
+#[test]
+fn expression_char() {
+    assert_eq!(
+        expression.parse("'a' == '\\n'"),
+        Some(Result {
+            source: "",
+            value: Node::Equal(Box::new(Node::Char('a')), Box::new(Node::Char('\n'))),
+        })
+    );
+}
+
+#[test]
+fn string_escapes() {
+    assert_eq!(
+        string.parse(r#""a\"b\n'c" x"#),
+        Some(Result {
+            source: "x",
+            value: Node::String("a\"b\n'c".to_string()),
+        })
+    );
+    assert_eq!(
+        string.parse(r#""""#),
+        Some(Result {
+            source: "",
+            value: Node::String(String::new()),
+        })
+    );
+}
+
+#[test]
+fn string_invalid() {
+    assert_eq!(string.parse(r#""abc"#), None);
+    assert_eq!(string.parse("\"a\nb\""), None);
+    assert_eq!(string.parse(r#""\q""#), None);
+}
+
+#[test]
+fn string_interpolation() {
+    assert_eq!(
+        string.parse(r#""hello ${ name }!" x"#),
+        Some(Result {
+            source: "x",
+            value: Node::Interpolation(vec![
+                ast::StringPart::Literal("hello ".to_string()),
+                ast::StringPart::Expression(Node::Id("name".to_string())),
+                ast::StringPart::Literal("!".to_string()),
+            ]),
+        })
+    );
+    assert_eq!(
+        string.parse(r#""${a}${f("}")}""#),
+        Some(Result {
+            source: "",
+            value: Node::Interpolation(vec![
+                ast::StringPart::Expression(Node::Id("a".to_string())),
+                ast::StringPart::Expression(Node::Call {
+                    callee: id("f"),
+                    args: vec![Node::String("}".to_string())],
+                }),
+            ]),
+        })
+    );
+}
+
+#[test]
+fn string_escaped_interpolation() {
+    assert_eq!(
+        string.parse(r#""cost: \${price}""#),
+        Some(Result {
+            source: "",
+            value: Node::String("cost: ${price}".to_string()),
+        })
+    );
+    assert_eq!(string.parse(r#""${x""#), None);
+    assert_eq!(string.parse(r#""${x"}"#), None);
+    assert_eq!(string.parse(r#""${}""#), None);
+}
+
+#[test]
+fn expression_precedence() {
+    assert_eq!(
+        expression.parse("1 + 2 * 3 == 7"),
+        Some(Result {
+            source: "",
+            value: Node::Equal(
+                Box::new(Node::Add(num(1), Box::new(Node::Multiply(num(2), num(3))))),
+                num(7)
+            ),
+        })
+    );
+}
+
+#[test]
+fn expression_modulo() {
+    assert_eq!(
+        expression.parse("1 + 7 % 3 * 2"),
+        Some(Result {
+            source: "",
+            value: Node::Add(
+                num(1),
+                Box::new(Node::Multiply(
+                    Box::new(Node::Modulo(num(7), num(3))),
+                    num(2)
+                ))
+            ),
+        })
+    );
+}
+
+#[test]
+fn expression_left_associative() {
+    assert_eq!(
+        expression.parse("8 - 4 - 2"),
+        Some(Result {
+            source: "",
+            value: Node::Subtract(Box::new(Node::Subtract(num(8), num(4))), num(2)),
+        })
+    );
+}
+
+#[test]
+fn expression_associativity() {
+    let levels = [
+        &["|"][..],
+        &["^"],
+        &["&"],
+        &["==", "!="],
+        &["<<", ">>"],
+        &["+", "-"],
+        &["*", "/", "%"],
+    ];
+
+    for operators in levels {
+        for first in operators {
+            for second in operators {
+                assert_eq!(
+                    parse_expression(&format!("a {first} b {second} c")),
+                    parse_expression(&format!("(a {first} b) {second} c")),
+                    "{first} {second}"
+                );
+            }
+        }
+    }
+
+    assert_eq!(
+        parse_expression("a ** b ** c"),
+        parse_expression("a ** (b ** c)")
+    );
+}
+
+#[test]
+fn expression_parentheses() {
+    assert_eq!(
+        expression.parse("(1 + 2) * !x"),
+        Some(Result {
+            source: "",
+            value: Node::Multiply(
+                Box::new(Node::Add(num(1), num(2))),
+                Box::new(Node::Not(id("x")))
+            ),
+        })
+    );
+}
+
+#[test]
+fn expression_bitwise() {
+    assert_eq!(
+        expression.parse("a | b ^ c & d == ~e"),
+        Some(Result {
+            source: "",
+            value: Node::BitOr(
+                id("a"),
+                Box::new(Node::BitXor(
+                    id("b"),
+                    Box::new(Node::BitAnd(
+                        id("c"),
+                        Box::new(Node::Equal(id("d"), Box::new(Node::BitNot(id("e")))))
+                    ))
+                ))
+            ),
+        })
+    );
+
+    assert_eq!(
+        expression.parse("a && b"),
+        Some(Result {
+            source: "&& b",
+            value: Node::Id("a".to_string()),
+        })
+    );
+}
+
+#[test]
+fn expression_shift() {
+    assert_eq!(
+        expression.parse("1 << 2 + 3 >> x == 4"),
+        Some(Result {
+            source: "",
+            value: Node::Equal(
+                Box::new(Node::ShiftRight(
+                    Box::new(Node::ShiftLeft(num(1), Box::new(Node::Add(num(2), num(3))))),
+                    id("x")
+                )),
+                num(4)
+            ),
+        })
+    );
+}
+
+#[test]
+fn expression_call() {
+    assert_eq!(
+        expression.parse("f() + g(1, x)"),
+        Some(Result {
+            source: "",
+            value: Node::Add(
+                Box::new(Node::Call {
+                    callee: Box::new(Node::Id("f".to_string())),
+                    args: vec![],
+                }),
+                Box::new(Node::Call {
+                    callee: Box::new(Node::Id("g".to_string())),
+                    args: vec![Node::Number(1), Node::Id("x".to_string())],
+                }),
+            ),
+        })
+    );
+}
+
+#[test]
+fn expression_power() {
+    assert_eq!(
+        expression.parse("2 ** 3 ** 2"),
+        Some(Result {
+            source: "",
+            value: Node::Power(num(2), Box::new(Node::Power(num(3), num(2)))),
+        })
+    );
+    assert_eq!(
+        expression.parse("~2 ** 2 * 3"),
+        Some(Result {
+            source: "",
+            value: Node::Multiply(
+                Box::new(Node::BitNot(Box::new(Node::Power(num(2), num(2))))),
+                num(3)
+            ),
+        })
+    );
+}
+
+#[test]
+fn expression_power_token() {
+    assert_eq!(
+        expression.parse("2 * 3"),
+        Some(Result {
+            source: "",
+            value: Node::Multiply(num(2), num(3)),
+        })
+    );
+    assert_eq!(
+        expression.parse("2**3"),
+        Some(Result {
+            source: "",
+            value: Node::Power(num(2), num(3)),
+        })
+    );
+    assert_eq!(
+        expression.parse("2 * * 3"),
+        Some(Result {
+            source: "* * 3",
+            value: Node::Number(2),
+        })
+    );
+}
+
+#[test]
+fn expression_call_chained() {
+    assert_eq!(
+        expression.parse("f(1)(2)"),
+        Some(Result {
+            source: "",
+            value: Node::Call {
+                callee: Box::new(Node::Call {
+                    callee: id("f"),
+                    args: vec![Node::Number(1)],
+                }),
+                args: vec![Node::Number(2)],
+            },
+        })
+    );
+}
+
+#[test]
+fn expression_index() {
+    assert_eq!(
+        expression.parse("xs[i + 1][0](2) * 3"),
+        Some(Result {
+            source: "",
+            value: Node::Multiply(
+                Box::new(Node::Call {
+                    callee: Box::new(Node::Index {
+                        target: Box::new(Node::Index {
+                            target: id("xs"),
+                            index: Box::new(Node::Add(id("i"), num(1))),
+                        }),
+                        index: num(0),
+                    }),
+                    args: vec![Node::Number(2)],
+                }),
+                num(3)
+            ),
+        })
+    );
+    assert_eq!(
+        expression.parse("xs[]"),
+        Some(Result {
+            source: "[]",
+            value: Node::Id("xs".to_string()),
+        })
+    );
+}
+
+#[test]
+fn expression_member() {
+    let member = |target, field: &str| {
+        Box::new(Node::Member {
+            target,
+            field: field.to_string(),
+        })
+    };
+
+    assert_eq!(
+        parse_expression("a.b.c"),
+        Ok(*member(member(id("a"), "b"), "c"))
+    );
+    assert_eq!(
+        parse_expression("f().x + 1"),
+        Ok(Node::Add(
+            member(
+                Box::new(Node::Call {
+                    callee: id("f"),
+                    args: vec![],
+                }),
+                "x"
+            ),
+            num(1)
+        ))
+    );
+    assert_eq!(
+        parse_expression("a . b(1)[0]"),
+        Ok(Node::Index {
+            target: Box::new(Node::Call {
+                callee: member(id("a"), "b"),
+                args: vec![Node::Number(1)],
+            }),
+            index: num(0),
+        })
+    );
+}
+
+#[test]
+fn member_numeric_field() {
+    assert_eq!(
+        expression.parse("a.1"),
+        Some(Result {
+            source: ".1",
+            value: Node::Id("a".to_string()),
+        })
+    );
+    assert!(parse_expression("a.1").is_err());
+}
+
+#[test]
+fn expression_lambda() {
+    let lambda = Node::Lambda {
+        parameters: vec!["x".to_string()],
+        body: Box::new(Node::Block(vec![Node::Return(Box::new(Node::Add(
+            id("x"),
+            num(1),
+        )))])),
+    };
+
+    assert_eq!(
+        statement.parse("var inc = function(x) { return x + 1; };"),
+        Some(Result {
+            source: "",
+            value: Node::Var("inc".to_string(), Box::new(lambda.clone())),
+        })
+    );
+    assert_eq!(
+        statement.parse("(function (x) { return x + 1; })(2);"),
+        Some(Result {
+            source: "",
+            value: Node::Call {
+                callee: Box::new(lambda),
+                args: vec![Node::Number(2)],
+            },
+        })
+    );
+}
+
+#[test]
+fn expression_call_many_arguments() {
+    let args = (0..500)
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    assert_eq!(
+        expression.parse(&format!("f({args})")),
+        Some(Result {
+            source: "",
+            value: Node::Call {
+                callee: Box::new(Node::Id("f".to_string())),
+                args: (0..500).map(Node::Number).collect(),
+            },
+        })
+    );
+}
+
+#[test]
+fn expression_array() {
+    assert_eq!(
+        expression.parse("[1, [], x]"),
+        Some(Result {
+            source: "",
+            value: Node::Array(vec![
+                Node::Number(1),
+                Node::Array(vec![]),
+                Node::Id("x".to_string())
+            ]),
+        })
+    );
+}
+
+#[test]
+fn expression_array_signed() {
+    let negate = |value| Node::Negate(num(value));
+
+    assert_eq!(
+        parse_expression("[-1, -2, 3]"),
+        Ok(Node::Array(vec![negate(1), negate(2), Node::Number(3)]))
+    );
+    assert_eq!(
+        parse_expression("[1 -2]"),
+        Ok(Node::Array(vec![Node::Subtract(num(1), num(2))]))
+    );
+}
+
+#[test]
+fn expression_negate() {
+    assert_eq!(
+        parse_expression("a - -b * --c"),
+        Ok(Node::Subtract(
+            id("a"),
+            Box::new(Node::Multiply(
+                Box::new(Node::Negate(id("b"))),
+                Box::new(Node::Negate(Box::new(Node::Negate(id("c")))))
+            ))
+        ))
+    );
+    assert_eq!(
+        parse_expression("-2 ** 2"),
+        Ok(Node::Negate(Box::new(Node::Power(num(2), num(2)))))
+    );
+}
+
+#[test]
+fn expression_tuple() {
+    assert_eq!(parse_expression("(1)"), Ok(Node::Number(1)));
+    assert_eq!(
+        parse_expression("(1, 2)"),
+        Ok(Node::Tuple(vec![Node::Number(1), Node::Number(2)]))
+    );
+    assert_eq!(parse_expression("()"), Ok(Node::Tuple(vec![])));
+    assert_eq!(
+        parse_expression("((a, 1 + 2), (b))"),
+        Ok(Node::Tuple(vec![
+            Node::Tuple(vec![Node::Id("a".to_string()), Node::Add(num(1), num(2))]),
+            Node::Id("b".to_string()),
+        ]))
+    );
+    assert!(parse_expression("(1, 2").is_err());
+}
+
+#[test]
+fn expression_map() {
+    let string = |value: &str| Node::String(value.to_string());
+
+    assert_eq!(parse_expression("{:}"), Ok(Node::Map(vec![])));
+    assert_eq!(
+        parse_expression(r#"{ "a": 1 }"#),
+        Ok(Node::Map(vec![(string("a"), Node::Number(1))]))
+    );
+    assert_eq!(
+        parse_expression(r#"{ "a": 1, x + 1: [2], "c": {:} }"#),
+        Ok(Node::Map(vec![
+            (string("a"), Node::Number(1)),
+            (
+                Node::Add(id("x"), num(1)),
+                Node::Array(vec![Node::Number(2)])
+            ),
+            (string("c"), Node::Map(vec![])),
+        ]))
+    );
+    assert_eq!(parse_expression(r#"{ "a": 1, }"#).ok(), None);
+    assert_eq!(parse_expression(r#"{ "a" 1 }"#).ok(), None);
+}
+
+#[test]
+fn map_or_block() {
+    assert_eq!(parse_expression("{}"), Ok(Node::Block(vec![])));
+    assert_eq!(
+        parse_program(r#"{ x } { x: 1 }; {:};"#),
+        Ok(Node::Block(vec![
+            Node::BlockExpression {
+                statements: vec![],
+                value: id("x"),
+            },
+            Node::Map(vec![(Node::Id("x".to_string()), Node::Number(1))]),
+            Node::Map(vec![]),
+        ]))
+    );
+    assert_eq!(
+        parse_expression("{ { 1 }: 2 }"),
+        Ok(Node::Map(vec![(
+            Node::BlockExpression {
+                statements: vec![],
+                value: num(1),
+            },
+            Node::Number(2)
+        )]))
+    );
+    assert_eq!(parse_expression("{ x; y: 1 }").ok(), None);
+}
+
+#[test]
+fn expression_recover_with() {
+    let parser = || {
+        left_paren_t
+            .and(expression.recover_with(Node::Number(0)))
+            .bind(|expr| right_paren_t.value(expr))
+    };
+
+    assert_eq!(
+        with_diagnostics(parser(), "( ) + 1"),
+        (
+            Some(Result {
+                source: "+ 1",
+                value: Node::Number(0),
+            }),
+            vec![ParseError {
+                kind: ParseErrorKind::Syntax,
+                offset: 2,
+                message: "unexpected `)`".to_string(),
+                expected: Vec::new(),
+                context: Vec::new(),
+            }]
+        )
+    );
+    assert_eq!(
+        with_diagnostics(parser(), "(x)"),
+        (
+            Some(Result {
+                source: "",
+                value: Node::Id("x".to_string()),
+            }),
+            vec![]
+        )
+    );
+}
+
+#[test]
+fn program_empty() {
+    let indent_blocks = ParseOptions {
+        indent_blocks: true,
+        ..ParseOptions::default()
+    };
+
+    for source in ["", "   ", "// just a comment", " /* a */\n\t// b\n"] {
+        assert_eq!(parse_program(source), Ok(Node::Block(vec![])), "{source:?}");
+        assert_eq!(
+            indent_blocks.parse_program(source),
+            Ok(Node::Block(vec![])),
+            "{source:?}"
+        );
+        assert_eq!(parse_resilient(source), (Node::Block(vec![]), vec![]));
+        assert_eq!(crate::parser::statements(source).count(), 0);
+    }
+}
+
+#[test]
+fn expression_invalid() {
+    assert_eq!(expression.parse(""), None);
+    assert_eq!(expression.parse("+ 1"), None);
+    assert_eq!(expression.parse("(1 + 2"), None);
+    assert_eq!(expression.parse("[1, 2"), None);
+}
+
+#[test]
+fn statement_if() {
+    assert_eq!(
+        statement.parse("if (x) return 1; else { return 2; }"),
+        Some(Result {
+            source: "",
+            value: Node::If(ast::If {
+                condition: id("x"),
+                consequence: Box::new(Node::Return(num(1))),
+                alternative: Box::new(Node::Block(vec![Node::Return(num(2))])),
+            }),
+        })
+    );
+
+    assert_eq!(
+        statement.parse("if (x) { }"),
+        Some(Result {
+            source: "",
+            value: Node::If(ast::If {
+                condition: id("x"),
+                consequence: Box::new(Node::Block(vec![])),
+                alternative: Box::new(Node::Block(vec![])),
+            }),
+        })
+    );
+}
+
+#[test]
+fn statement_while() {
+    assert_eq!(
+        statement.parse("while (x != 0) x = x - 1;"),
+        Some(Result {
+            source: "",
+            value: Node::While(ast::While {
+                condition: Box::new(Node::NotEqual(id("x"), num(0))),
+                body: Box::new(Node::Assignment(
+                    "x".to_string(),
+                    Box::new(Node::Subtract(id("x"), num(1)))
+                )),
+                alternative: None,
+            }),
+        })
+    );
+}
+
+#[test]
+fn statement_while_else() {
+    assert_eq!(
+        statement.parse("while (x) x = 0; else { return 1; }"),
+        Some(Result {
+            source: "",
+            value: Node::While(ast::While {
+                condition: id("x"),
+                body: Box::new(Node::Assignment("x".to_string(), num(0))),
+                alternative: Some(Box::new(Node::Block(vec![Node::Return(num(1))]))),
+            }),
+        })
+    );
+    assert_eq!(
+        statement.parse("while (x) x = 0; else { }"),
+        statement.parse("while (x) x = 0;")
+    );
+    assert_eq!(
+        statement
+            .parse("while (x) x = 0; else x = 1;")
+            .map(|res| res.source),
+        Some("else x = 1;")
+    );
+}
+
+#[test]
+fn statement_block() {
+    assert_eq!(
+        statement.parse("{ x = 1; }"),
+        Some(Result {
+            source: "",
+            value: Node::Block(vec![Node::Assignment("x".to_string(), num(1))]),
+        })
+    );
+    assert_eq!(statement.parse("{ x = 1 }"), None);
+    assert_eq!(
+        statement.parse("{ 1 };"),
+        Some(Result {
+            source: "",
+            value: Node::BlockExpression {
+                statements: vec![],
+                value: num(1),
+            },
+        })
+    );
+    assert_eq!(
+        parse_program("{ x } - 1;"),
+        Ok(Node::Block(vec![
+            Node::BlockExpression {
+                statements: vec![],
+                value: id("x"),
+            },
+            Node::Negate(num(1)),
+        ]))
+    );
+}
+
+#[test]
+fn expression_block() {
+    assert_eq!(
+        statement.parse("var x = { var t = 1; t + 1 };"),
+        Some(Result {
+            source: "",
+            value: Node::Var(
+                "x".to_string(),
+                Box::new(Node::BlockExpression {
+                    statements: vec![Node::Var("t".to_string(), num(1))],
+                    value: Box::new(Node::Add(id("t"), num(1))),
+                })
+            ),
+        })
+    );
+    assert_eq!(
+        expression.parse("{ 1; }"),
+        Some(Result {
+            source: "",
+            value: Node::Block(vec![Node::Number(1)]),
+        })
+    );
+    assert_eq!(
+        expression.parse("{ 1 }"),
+        Some(Result {
+            source: "",
+            value: Node::BlockExpression {
+                statements: vec![],
+                value: num(1),
+            },
+        })
+    );
+    assert_eq!(
+        expression.parse("{ } + 1"),
+        Some(Result {
+            source: "",
+            value: Node::Add(Box::new(Node::Block(vec![])), num(1)),
+        })
+    );
+}
+
+#[test]
+fn statement_var_and_assignment() {
+    assert_eq!(
+        statement.parse("var x = 1;"),
+        Some(Result {
+            source: "",
+            value: Node::Var("x".to_string(), num(1)),
+        })
+    );
+
+    assert_eq!(
+        statement.parse("x = 2;"),
+        Some(Result {
+            source: "",
+            value: Node::Assignment("x".to_string(), num(2)),
+        })
+    );
+
+    assert_eq!(
+        statement.parse("x == 2;"),
+        Some(Result {
+            source: "",
+            value: Node::Equal(id("x"), num(2)),
+        })
+    );
+}
+
+#[test]
+fn statement_destructure() {
+    assert_eq!(
+        statement.parse("var (a, b) = (1, 2);"),
+        Some(Result {
+            source: "",
+            value: Node::Destructure {
+                pattern: vec!["a".to_string(), "b".to_string()],
+                value: Box::new(Node::Tuple(vec![Node::Number(1), Node::Number(2)])),
+            },
+        })
+    );
+    assert_eq!(
+        parse_program("var (a, b) = 1").map_err(|error| error.kind),
+        Err(ParseErrorKind::Syntax)
+    );
+}
+
+#[test]
+fn statement_function() {
+    assert_eq!(
+        statement.parse("function add(a, b) { return a + b; }"),
+        Some(Result {
+            source: "",
+            value: Node::Function(ast::Function {
+                name: "add".to_string(),
+                parameters: vec!["a".to_string(), "b".to_string()],
+                body: Box::new(Node::Block(vec![Node::Return(Box::new(Node::Add(
+                    id("a"),
+                    id("b")
+                )))])),
+            }),
+        })
+    );
+}
+
+#[test]
+fn statement_missing_semicolon() {
+    assert_eq!(statement.parse("return 1"), None);
+    assert_eq!(statement.parse("var x = 1"), None);
+}
+
+#[test]
+fn program_valid() {
+    assert_eq!(
+        parse_program(" // comment\n function main() { return 0; }\n main();"),
+        Ok(Node::Block(vec![
+            Node::Function(ast::Function {
+                name: "main".to_string(),
+                parameters: vec![],
+                body: Box::new(Node::Block(vec![Node::Return(num(0))])),
+            }),
+            Node::Call {
+                callee: Box::new(Node::Id("main".to_string())),
+                args: vec![],
+            },
+        ]))
+    );
+}
+
+#[test]
+fn program_globals() {
+    assert_eq!(
+        parse_program("var limit = 3; function f() { return limit; }"),
+        Ok(Node::Block(vec![
+            Node::Var("limit".to_string(), num(3)),
+            Node::Function(ast::Function {
+                name: "f".to_string(),
+                parameters: vec![],
+                body: Box::new(Node::Block(vec![Node::Return(id("limit"))])),
+            }),
+        ]))
+    );
+}
+
+#[test]
+fn program_invalid() {
+    assert_eq!(
+        parse_program("var x = ;").map_err(|error| error.kind),
+        Err(ParseErrorKind::Syntax)
+    );
+}
+
+#[test]
+fn program_nesting_within_limit() {
+    let source = format!("{}1{};", "(".repeat(100), ")".repeat(100));
+    assert_eq!(
+        parse_program(&source),
+        Ok(Node::Block(vec![Node::Number(1)]))
+    );
+}
+
+// Each item of a block is parsed once, so nesting blocks doesn't multiply
+// the work.
+#[test]
+fn program_nested_block_values() {
+    let source = format!("var y = {}1{};", "{ 1 + ".repeat(40), " }".repeat(40));
+    assert!(parse_program(&source).is_ok());
+
+    let source = format!("var y = {}1{};", "f({ ".repeat(40), " })".repeat(40));
+    assert!(parse_program(&source).is_ok());
+}
+
+#[test]
+fn program_recursion_limit() {
+    let source = format!("{}1{};", "(".repeat(10_000), ")".repeat(10_000));
+    assert_eq!(
+        parse_program(&source).map_err(|error| error.kind),
+        Err(ParseErrorKind::RecursionLimit)
+    );
+
+    let source = format!("{}x;", "!".repeat(10_000));
+    assert_eq!(
+        parse_program(&source).map_err(|error| error.kind),
+        Err(ParseErrorKind::RecursionLimit)
+    );
+
+    let source = format!("2{};", " ** 2".repeat(10_000));
+    assert_eq!(
+        parse_program(&source).map_err(|error| error.kind),
+        Err(ParseErrorKind::RecursionLimit)
+    );
+
+    let source = format!("{}{};", "{".repeat(10_000), "}".repeat(10_000));
+    assert_eq!(
+        parse_program(&source).map_err(|error| error.kind),
+        Err(ParseErrorKind::RecursionLimit)
+    );
+}
+
+#[test]
+fn program_custom_recursion_limit() {
+    let options = ParseOptions {
+        recursion_limit: 8,
+        ..Default::default()
+    };
+    assert_eq!(
+        options
+            .parse_program("((((((((1))))))));")
+            .map_err(|error| error.kind),
+        Err(ParseErrorKind::RecursionLimit)
+    );
+    assert_eq!(
+        options.parse_program("((1));"),
+        Ok(Node::Block(vec![Node::Number(1)]))
+    );
+}
+
+#[test]
+fn program_keywords_without_whitespace() {
+    let cond = || id("cond");
+
+    assert_eq!(
+        parse_program("if(cond){}"),
+        Ok(Node::Block(vec![Node::If(ast::If {
+            condition: cond(),
+            consequence: Box::new(Node::Block(vec![])),
+            alternative: Box::new(Node::Block(vec![])),
+        })]))
+    );
+    assert_eq!(
+        parse_program("while(cond){}"),
+        Ok(Node::Block(vec![Node::While(ast::While {
+            condition: cond(),
+            body: Box::new(Node::Block(vec![])),
+            alternative: None,
+        })]))
+    );
+    assert_eq!(
+        parse_program("function f(x){if(x){return(x);}else{return-x;}}"),
+        parse_program("function f(x) { if (x) { return (x); } else { return -x; } }")
+    );
+
+    // A keyword followed by more of an identifier is just an identifier.
+    assert_eq!(
+        parse_program("iffy(cond);"),
+        Ok(Node::Block(vec![Node::Call {
+            callee: id("iffy"),
+            args: vec![Node::Id("cond".to_string())],
+        }]))
+    );
+}
+
+#[test]
+fn program_line_continuations() {
+    let options = ParseOptions {
+        line_continuations: true,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        options.parse_expression("1 + \\\n 2"),
+        Ok(Node::Add(num(1), num(2)))
+    );
+    assert_eq!(
+        options.parse_expression("1 +\\\r\n2"),
+        Ok(Node::Add(num(1), num(2)))
+    );
+    assert_eq!(
+        options.parse_expression("1 \\ + 2"),
+        Err(ParseError {
+            kind: ParseErrorKind::Syntax,
+            offset: 2,
+            message: "unexpected `\\\\`".to_string(),
+            expected: Vec::new(),
+            context: Vec::new(),
+        })
+    );
+    assert!(options.parse_expression("1 + 2 \\").is_err());
+    assert!(parse_expression("1 + \\\n 2").is_err());
+
+    // The indented continuation line doesn't open a block.
+    let indent_blocks = ParseOptions {
+        indent_blocks: true,
+        ..options
+    };
+    assert_eq!(
+        indent_blocks.parse_program("function f(a)\n    return a + \\\n        1;\nf(1);"),
+        parse_program("function f(a) { return a + 1; } f(1);")
+    );
+}
+
+#[test]
+fn expression_int_divide() {
+    let options = ParseOptions {
+        comments: CommentStyle {
+            double_slash: false,
+            hash: true,
+            block: true,
+        },
+        int_division: true,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        options.parse_expression("a // b / c * d"),
+        Ok(Node::Multiply(
+            Box::new(Node::Divide(
+                Box::new(Node::IntDivide(id("a"), id("b"))),
+                id("c")
+            )),
+            id("d")
+        ))
+    );
+    assert_eq!(
+        options.parse_expression("a + b // c"),
+        Ok(Node::Add(
+            id("a"),
+            Box::new(Node::IntDivide(id("b"), id("c")))
+        ))
+    );
+
+    // By default `//` starts a comment.
+    assert_eq!(parse_expression("a // b"), Ok(Node::Id("a".to_string())));
+    assert_eq!(
+        ParseOptions {
+            int_division: false,
+            ..options.clone()
+        }
+        .parse_expression("a // b")
+        .map_err(|error| error.kind),
+        Err(ParseErrorKind::Syntax)
+    );
+    assert_eq!(
+        ParseOptions {
+            int_division: true,
+            ..Default::default()
+        }
+        .parse_expression("a // b"),
+        Err(ParseError {
+            kind: ParseErrorKind::InvalidOptions,
+            offset: 0,
+            message: "`//` can't be both integer division and a comment".to_string(),
+            expected: Vec::new(),
+            context: Vec::new(),
+        })
+    );
+}
+
+#[test]
+fn program_chained_comparisons() {
+    let options = ParseOptions {
+        reject_chained_comparisons: true,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        options.parse_program("var x = 1 == 2 != 3;"),
+        Err(ParseError {
+            kind: ParseErrorKind::Syntax,
+            offset: 15,
+            message: "comparison operators can't be chained".to_string(),
+            expected: Vec::new(),
+            context: Vec::new(),
+        })
+    );
+    assert!(options.parse_program("a == b + c == d;").is_err());
+
+    for source in [
+        "a == b & b == c;",
+        "(a == b) == c;",
+        "a == (b == c);",
+        "f(a == b, c == d);",
+    ] {
+        assert!(options.parse_program(source).is_ok(), "{source}");
+    }
+
+    // Off by default.
+    assert!(parse_program("var x = 1 == 2 != 3;").is_ok());
+}
+
+#[test]
+fn program_max_identifier_len() {
+    let options = ParseOptions {
+        max_identifier_len: Some(5),
+        ..Default::default()
+    };
+
+    assert!(
+        options
+            .parse_program("var short = 1; return short;")
+            .is_ok()
+    );
+    assert_eq!(
+        options.parse_program("var x = 1 + toolong;"),
+        Err(ParseError {
+            kind: ParseErrorKind::Syntax,
+            offset: 12,
+            message: "identifier is longer than the limit of 5 characters".to_string(),
+            expected: Vec::new(),
+            context: Vec::new(),
+        })
+    );
+    // Counted in characters, not bytes.
+    assert!(options.parse_program("var čšž = 1;").is_ok());
+    // Keywords and number suffixes aren't identifiers.
+    assert!(options.parse_program("function f() { return 1; }").is_ok());
+    let options = ParseOptions {
+        max_identifier_len: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(
+        options.parse_program("var x = 10i64;"),
+        Ok(Node::Block(vec![Node::Var(
+            "x".to_string(),
+            Box::new(Node::TypedNumber {
+                value: 10,
+                suffix: IntWidth::I64
+            })
+        )]))
+    );
+
+    // Unlimited by default.
+    assert!(parse_program("var x = 1 + toolong;").is_ok());
+}
+
+#[test]
+fn program_indent_blocks() {
+    let options = ParseOptions {
+        indent_blocks: true,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        options.parse_program(
+            "
+function f(a)
+    while (a != 0)
+        a = a - 1;
+        g(a);
+    return a;
+f(1);
+            "
+        ),
+        Ok(Node::Block(vec![
+            Node::Function(ast::Function {
+                name: "f".to_string(),
+                parameters: vec!["a".to_string()],
+                body: Box::new(Node::Block(vec![
+                    Node::While(ast::While {
+                        condition: Box::new(Node::NotEqual(id("a"), num(0))),
+                        body: Box::new(Node::Block(vec![
+                            Node::Assignment(
+                                "a".to_string(),
+                                Box::new(Node::Subtract(id("a"), num(1)))
+                            ),
+                            Node::Call {
+                                callee: id("g"),
+                                args: vec![Node::Id("a".to_string())],
+                            },
+                        ])),
+                        alternative: None,
+                    }),
+                    Node::Return(id("a")),
+                ])),
+            }),
+            Node::Call {
+                callee: id("f"),
+                args: vec![Node::Number(1)],
+            },
+        ]))
+    );
+
+    assert_eq!(
+        options.parse_program("if (a)\n        x = 1;\n    y = 2;\n"),
+        Err(ParseError {
+            kind: ParseErrorKind::Syntax,
+            offset: 26,
+            message: "dedent doesn't match any outer indentation level".to_string(),
+            expected: Vec::new(),
+            context: Vec::new(),
+        })
+    );
+    assert_eq!(
+        options
+            .parse_program("if (a)\n    var x 1;\n")
+            .map_err(|error| error.offset),
+        Err(17)
+    );
+    assert_eq!(
+        options.parse_program("x;\n/* a\n   b */\nz;"),
+        Ok(Node::Block(vec![
+            Node::Id("x".to_string()),
+            Node::Id("z".to_string()),
+        ]))
+    );
+}
+
+#[test]
+fn program_memoized() {
+    let memoized = ParseOptions {
+        memoize: true,
+        ..Default::default()
+    };
+
+    let nested = format!("{}[x, 1]{};", "f(g(1), (".repeat(60), "))".repeat(60));
+    let source = format!("{nested}\nx = {nested}\nreturn {nested}");
+    assert!(parse_program(&source).is_ok());
+    assert_eq!(memoized.parse_program(&source), parse_program(&source));
+
+    let unclosed = format!("{}1;", "f(g(1), (".repeat(60));
+    assert_eq!(
+        memoized
+            .parse_program(&unclosed)
+            .map_err(|error| error.kind),
+        Err(ParseErrorKind::Syntax)
+    );
+}
+
+#[test]
+fn parse_expression_valid() {
+    assert_eq!(
+        parse_expression(" 1 + 2 * 3 // trailing comment"),
+        Ok(Node::Add(num(1), Box::new(Node::Multiply(num(2), num(3)))))
+    );
+}
+
+#[test]
+fn parse_expression_invalid() {
+    assert_eq!(
+        parse_expression("1 +").map_err(|error| error.kind),
+        Err(ParseErrorKind::Syntax)
+    );
+    assert_eq!(
+        parse_expression("1 2").map_err(|error| error.kind),
+        Err(ParseErrorKind::Syntax)
+    );
+    assert_eq!(
+        parse_expression("1;").map_err(|error| error.kind),
+        Err(ParseErrorKind::Syntax)
+    );
+    assert_eq!(
+        parse_expression("").map_err(|error| error.kind),
+        Err(ParseErrorKind::Syntax)
+    );
+}
+
+#[test]
+fn parse_error_display() {
+    assert_eq!(
+        parse_expression("1 + 2 )"),
+        Err(ParseError {
+            kind: ParseErrorKind::Syntax,
+            offset: 6,
+            message: "unexpected `)`".to_string(),
+            expected: Vec::new(),
+            context: Vec::new(),
+        })
+    );
+    assert_eq!(
+        parse_expression("").unwrap_err().to_string(),
+        "unexpected end of input at offset 0"
+    );
+
+    let options = ParseOptions {
+        recursion_limit: 2,
+        ..Default::default()
+    };
+    assert_eq!(
+        options
+            .parse_expression("x + ((1))")
+            .unwrap_err()
+            .to_string(),
+        "nesting exceeds the recursion limit of 2 at offset 6"
+    );
+}
+
+#[test]
+fn parse_error_committed_statement() {
+    let error = |source| parse_program(source).map_err(|error| error.to_string());
+
+    assert_eq!(
+        error("x = 1;\nvar y 1;"),
+        Err("unexpected `1` at offset 13".to_string())
+    );
+    assert_eq!(
+        error("function f(a { return a; }"),
+        Err("unexpected `{` at offset 13".to_string())
+    );
+    assert_eq!(
+        error("function f() return 1;"),
+        Err("unexpected `r` at offset 13".to_string())
+    );
+    assert_eq!(
+        parse_program("function (x) { return x; }(1);"),
+        Ok(Node::Block(vec![Node::Call {
+            callee: Box::new(Node::Lambda {
+                parameters: vec!["x".to_string()],
+                body: Box::new(Node::Block(vec![Node::Return(id("x"))])),
+            }),
+            args: vec![Node::Number(1)],
+        }]))
+    );
+}
+
+#[test]
+fn resilient_broken_function() {
+    let source = "
+        function first(a) { return a; }
+        function broken(a { if (a) { return 1; } return 2; }
+        // comment ; with { braces
+        function last() { return 3; }
+    ";
+    let (program, errors) = parse_resilient(source);
+
+    let Node::Block(items) = program else {
+        panic!("program is not a block");
+    };
+    assert_eq!(items.len(), 3);
+    assert!(matches!(&items[0], Node::Function(function) if function.name == "first"));
+    assert_eq!(items[1], Node::Error);
+    assert!(matches!(&items[2], Node::Function(function) if function.name == "last"));
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].offset, source.find("{ if").unwrap());
+}
+
+#[test]
+fn resilient_statements() {
+    assert_eq!(
+        parse_resilient("x = 1; var x 2; } y;"),
+        (
+            Node::Block(vec![
+                Node::Assignment("x".to_string(), num(1)),
+                Node::Error,
+                Node::Error,
+                Node::Id("y".to_string()),
+            ]),
+            vec![
+                ParseError {
+                    kind: ParseErrorKind::Syntax,
+                    offset: 13,
+                    message: "unexpected `2`".to_string(),
+                    expected: Vec::new(),
+                    context: Vec::new(),
+                },
+                ParseError {
+                    kind: ParseErrorKind::Syntax,
+                    offset: 16,
+                    message: "unexpected `}`".to_string(),
+                    expected: Vec::new(),
+                    context: Vec::new(),
+                },
+            ]
+        )
+    );
+    assert_eq!(
+        parse_resilient(" // nothing"),
+        (Node::Block(vec![]), vec![])
+    );
+}
+
+#[test]
+fn parse_error_escapes_input() {
+    assert_eq!(
+        parse_program("x = 1;\x01").map_err(|error| error.message),
+        Err("unexpected `\\x01`".to_string())
+    );
+}
+
+#[test]
+fn parse_error_question_mark() {
+    fn parse(source: &str) -> std::result::Result<Node, Box<dyn std::error::Error>> {
+        let node = parse_program(source)?;
+        Ok(node)
+    }
+
+    assert!(parse("x = 1;").is_ok());
+    assert_eq!(
+        parse("x = 1; 2 3;").unwrap_err().to_string(),
+        "unexpected `2` at offset 7"
+    );
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn big_number_literal() {
+    let digits = "1234567890123456789012345678901234567890";
+    assert_eq!(
+        parse_expression(digits),
+        Ok(Node::BigNumber(
+            crate::bigint::BigInt::from_str_radix(digits, 10).unwrap()
+        ))
+    );
+    assert_eq!(parse_expression(digits).unwrap().to_string(), digits);
+    assert_eq!(
+        parse_expression("0x10000000000000000 + 1"),
+        Ok(Node::Add(
+            Box::new(Node::BigNumber(
+                crate::bigint::BigInt::from_str_radix("18446744073709551616", 10).unwrap()
+            )),
+            num(1),
+        ))
+    );
+}
@@ -0,0 +1,292 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use super::{CommentStyle, NumberConfig, ParseError, ParseOptions, Parser, Result};
+use crate::ast::Node;
+
+struct Context {
+    options: ParseOptions,
+    input_len: Option<usize>,
+    depth: usize,
+    error: Option<ParseError>,
+    // Errors recovered from, which didn't abort the parse.
+    diagnostics: Vec<ParseError>,
+    // Names that failed alternatives expected, and the offset they expected
+    // them at. Only the furthest offset is kept, as that's where parsing
+    // fails.
+    expected: Option<(usize, Vec<&'static str>)>,
+    // Whether an entry point picks up `error` once parsing returns.
+    reporting: bool,
+    memo: HashMap<MemoKey, Option<(usize, Node)>>,
+}
+
+impl Context {
+    fn new(options: ParseOptions) -> Self {
+        Self {
+            options,
+            input_len: None,
+            depth: 0,
+            error: None,
+            diagnostics: Vec::new(),
+            expected: None,
+            reporting: false,
+            memo: HashMap::new(),
+        }
+    }
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Context> = RefCell::new(Context::new(ParseOptions::default()));
+}
+
+/// Runs `f` with a fresh parsing context configured by `options`. Returns the
+/// result of `f` together with the error that aborted parsing, if any.
+pub(super) fn with_options<T>(
+    options: &ParseOptions,
+    f: impl FnOnce() -> T,
+) -> (T, Option<ParseError>) {
+    let mut context = Context::new(options.clone());
+    context.reporting = true;
+    let previous = CONTEXT.replace(context);
+    let res = f();
+    let context = CONTEXT.replace(previous);
+    (res, context.error)
+}
+
+/// Runs `f` with `input` as the source that offsets are measured against,
+/// unless an enclosing parse already registered its input.
+pub(super) fn with_input<T>(input: &str, f: impl FnOnce() -> T) -> T {
+    let registered = CONTEXT.with_borrow_mut(|ctx| {
+        if ctx.input_len.is_some() {
+            return false;
+        }
+
+        ctx.input_len = Some(input.len());
+        true
+    });
+
+    let res = f();
+    if registered {
+        CONTEXT.with_borrow_mut(|ctx| {
+            ctx.input_len = None;
+            ctx.memo.clear();
+        });
+    }
+
+    res
+}
+
+/// Byte offset of `source` (a suffix of the registered input) within the
+/// input.
+pub(super) fn offset(source: &str) -> usize {
+    CONTEXT.with_borrow(|ctx| ctx.input_len.unwrap_or(source.len()) - source.len())
+}
+
+pub(super) fn number_config() -> NumberConfig {
+    CONTEXT.with_borrow(|ctx| ctx.options.number)
+}
+
+pub(super) fn comment_style() -> CommentStyle {
+    CONTEXT.with_borrow(|ctx| ctx.options.comments)
+}
+
+pub(super) fn line_continuations() -> bool {
+    CONTEXT.with_borrow(|ctx| ctx.options.line_continuations)
+}
+
+pub(super) fn reject_chained_comparisons() -> bool {
+    CONTEXT.with_borrow(|ctx| ctx.options.reject_chained_comparisons)
+}
+
+pub(super) fn int_division() -> bool {
+    CONTEXT.with_borrow(|ctx| ctx.options.int_division)
+}
+
+pub(super) fn max_identifier_len() -> Option<usize> {
+    CONTEXT.with_borrow(|ctx| ctx.options.max_identifier_len)
+}
+
+/// Records `error` as the one aborting the parse, unless an earlier error
+/// already did. Outside of `with_options` nothing would report it, so it is
+/// dropped and the parser simply fails.
+pub(super) fn fail(error: ParseError) {
+    CONTEXT.with_borrow_mut(|ctx| {
+        if ctx.reporting {
+            ctx.error.get_or_insert(error);
+        }
+    });
+}
+
+/// Records `names` as expected at `offset`, in addition to names expected
+/// there before. Dropped outside of `with_options`, like `fail`.
+pub(super) fn expect(offset: usize, names: impl IntoIterator<Item = &'static str>) {
+    CONTEXT.with_borrow_mut(|ctx| {
+        if !ctx.reporting {
+            return;
+        }
+
+        let (at, expected) = ctx.expected.get_or_insert((offset, Vec::new()));
+        if *at < offset {
+            *at = offset;
+            expected.clear();
+        } else if *at > offset {
+            return;
+        }
+
+        for name in names {
+            if !expected.contains(&name) {
+                expected.push(name);
+            }
+        }
+    });
+}
+
+pub(super) fn expected_at(offset: usize) -> Vec<&'static str> {
+    CONTEXT.with_borrow(|ctx| match &ctx.expected {
+        Some((at, expected)) if *at == offset => expected.clone(),
+        _ => Vec::new(),
+    })
+}
+
+pub(super) fn failed() -> bool {
+    CONTEXT.with_borrow(|ctx| ctx.error.is_some())
+}
+
+/// Adds `rule` as the outermost context of the error aborting the parse, if
+/// there is one.
+pub(super) fn add_context(rule: &'static str) {
+    CONTEXT.with_borrow_mut(|ctx| {
+        if let Some(error) = &mut ctx.error {
+            error.context.insert(0, rule);
+        }
+    });
+}
+
+/// Records an error the parser recovered from. Like `fail`, it's dropped
+/// outside of `with_options`.
+pub(super) fn report(error: ParseError) {
+    CONTEXT.with_borrow_mut(|ctx| {
+        if ctx.reporting {
+            ctx.diagnostics.push(error);
+        }
+    });
+}
+
+pub(super) fn take_diagnostics() -> Vec<ParseError> {
+    CONTEXT.with_borrow_mut(|ctx| std::mem::take(&mut ctx.diagnostics))
+}
+
+fn enter(source: &str) -> bool {
+    CONTEXT.with_borrow_mut(|ctx| {
+        if ctx.error.is_some() {
+            return false;
+        }
+
+        if ctx.depth >= ctx.options.recursion_limit {
+            ctx.error = Some(ParseError::recursion_limit(
+                ctx.input_len.unwrap_or(source.len()) - source.len(),
+                ctx.options.recursion_limit,
+            ));
+            return false;
+        }
+
+        ctx.depth += 1;
+        true
+    })
+}
+
+fn exit() {
+    CONTEXT.with_borrow_mut(|ctx| ctx.depth -= 1);
+}
+
+/// Guards a recursive grammar rule against unbounded nesting.
+///
+/// Once the recursion limit is reached, the error is recorded in the context
+/// and every guarded parser fails until the top level parse returns.
+pub(super) struct Nested<P>(P);
+
+impl<'a, P> Nested<P>
+where
+    P: Parser<'a>,
+{
+    pub(super) fn new(parser: P) -> Self {
+        Nested(parser)
+    }
+}
+
+impl<'a, T, P> Parser<'a> for Nested<P>
+where
+    P: Parser<'a, Output = T>,
+{
+    type Output = T;
+
+    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
+        if !enter(source) {
+            return None;
+        }
+
+        let res = self.0.parse(source);
+        exit();
+        res
+    }
+}
+
+/// Caches the results of a grammar rule by input offset, so that
+/// backtracking never parses the same rule at the same position twice.
+///
+/// Memoization only kicks in when `ParseOptions::memoize` is set and an input
+/// is registered to measure offsets against.
+pub(super) struct Memoized<P> {
+    rule: &'static str,
+    parser: P,
+}
+
+impl<'a, P> Memoized<P>
+where
+    P: Parser<'a, Output = Node>,
+{
+    pub(super) fn new(rule: &'static str, parser: P) -> Self {
+        Memoized { rule, parser }
+    }
+}
+
+impl<'a, P> Parser<'a> for Memoized<P>
+where
+    P: Parser<'a, Output = Node>,
+{
+    type Output = Node;
+
+    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
+        let Some(key) = memo_key(self.rule, source) else {
+            return self.parser.parse(source);
+        };
+
+        if let Some(entry) = memo_get(key) {
+            return entry.map(|(consumed, value)| Result {
+                source: &source[consumed..],
+                value,
+            });
+        }
+
+        let res = self.parser.parse(source);
+        memo_insert(key, source, res.as_ref());
+        res
+    }
+}
+
+type MemoKey = (&'static str, usize);
+
+fn memo_key(rule: &'static str, source: &str) -> Option<MemoKey> {
+    CONTEXT.with_borrow(|ctx| {
+        let input_len = ctx.input_len.filter(|_| ctx.options.memoize)?;
+        Some((rule, input_len - source.len()))
+    })
+}
+
+fn memo_get(key: MemoKey) -> Option<Option<(usize, Node)>> {
+    CONTEXT.with_borrow(|ctx| ctx.memo.get(&key).cloned())
+}
+
+fn memo_insert(key: MemoKey, source: &str, res: Option<&Result<'_, Node>>) {
+    let entry = res.map(|res| (source.len() - res.source.len(), res.value.clone()));
+    CONTEXT.with_borrow_mut(|ctx| ctx.memo.insert(key, entry));
+}
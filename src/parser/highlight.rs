@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod tests;
+
+use super::{
+    OPERATORS, Result, char_literal_base, comments, digit_run, id_base, number_base_with,
+    string_char, whitespace,
+};
+use crate::ranges::IntWidth;
+use crate::span::Span;
+
+const KEYWORDS: [&str; 6] = ["function", "if", "else", "return", "var", "while"];
+
+const PUNCTUATION: [char; 10] = ['(', ')', '{', '}', '[', ']', ',', ';', ':', '.'];
+
+/// Class of a source region reported by [`highlight_tokens`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Keyword,
+    Identifier,
+    Number,
+    String,
+    Char,
+    Comment,
+    Operator,
+    Punctuation,
+    Whitespace,
+    /// A character that doesn't start any token, such as a stray `@` or the
+    /// opening quote of an unterminated string.
+    Unknown,
+}
+
+/// Splits `source` into classified regions for syntax highlighting.
+///
+/// The regions are contiguous and cover the whole source, so comments and
+/// whitespace are reported too. Interpolated expressions are part of the
+/// string token that contains them.
+pub fn highlight_tokens(source: &str) -> Vec<(Span, TokenKind)> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+
+    while let Some(ch) = rest.chars().next() {
+        let (kind, remaining) = match next_token(rest) {
+            Some(token) => token,
+            None => (TokenKind::Unknown, &rest[ch.len_utf8()..]),
+        };
+
+        let start = source.len() - rest.len();
+        let end = source.len() - remaining.len();
+        tokens.push((Span { start, end }, kind));
+        rest = remaining;
+    }
+
+    tokens
+}
+
+fn next_token(source: &str) -> Option<(TokenKind, &str)> {
+    if let Some(res) = whitespace(source) {
+        return Some((TokenKind::Whitespace, res.source));
+    }
+
+    if let Some(res) = comments(source) {
+        return Some((TokenKind::Comment, res.source));
+    }
+
+    if let Some(res) = id_base(source) {
+        let kind = if KEYWORDS.contains(&res.value) {
+            TokenKind::Keyword
+        } else {
+            TokenKind::Identifier
+        };
+        return Some((kind, res.source));
+    }
+
+    if let Some(res) = number_base_with(source, digit_run) {
+        // A width suffix is part of the literal.
+        let rest = id_base(res.source)
+            .filter(|suffix| IntWidth::from_suffix(suffix.value).is_some())
+            .map_or(res.source, |suffix| suffix.source);
+        return Some((TokenKind::Number, rest));
+    }
+
+    if let Some(rest) = string(source) {
+        return Some((TokenKind::String, rest));
+    }
+
+    if let Some(res) = char_literal_base(source) {
+        return Some((TokenKind::Char, res.source));
+    }
+
+    if let Some(operator) = OPERATORS.iter().find(|op| source.starts_with(*op)) {
+        return Some((TokenKind::Operator, &source[operator.len()..]));
+    }
+
+    let ch = source.chars().next()?;
+    PUNCTUATION
+        .contains(&ch)
+        .then(|| (TokenKind::Punctuation, &source[ch.len_utf8()..]))
+}
+
+fn string(source: &str) -> Option<&str> {
+    let mut rest = source.strip_prefix('"')?;
+    while let Some(Result { source, .. }) = string_char(rest) {
+        rest = source;
+    }
+
+    rest.strip_prefix('"')
+}
@@ -0,0 +1,86 @@
+use super::*;
+
+fn classify(source: &str) -> Vec<(&str, TokenKind)> {
+    highlight_tokens(source)
+        .into_iter()
+        .map(|(span, kind)| (&source[span.start..span.end], kind))
+        .collect()
+}
+
+#[test]
+fn comments_and_keywords() {
+    let source = "var x = 1; // var\n/* if */ if";
+    assert_eq!(
+        highlight_tokens(source),
+        vec![
+            (Span { start: 0, end: 3 }, TokenKind::Keyword),
+            (Span { start: 3, end: 4 }, TokenKind::Whitespace),
+            (Span { start: 4, end: 5 }, TokenKind::Identifier),
+            (Span { start: 5, end: 6 }, TokenKind::Whitespace),
+            (Span { start: 6, end: 7 }, TokenKind::Operator),
+            (Span { start: 7, end: 8 }, TokenKind::Whitespace),
+            (Span { start: 8, end: 9 }, TokenKind::Number),
+            (Span { start: 9, end: 10 }, TokenKind::Punctuation),
+            (Span { start: 10, end: 11 }, TokenKind::Whitespace),
+            (Span { start: 11, end: 18 }, TokenKind::Comment),
+            (Span { start: 18, end: 26 }, TokenKind::Comment),
+            (Span { start: 26, end: 27 }, TokenKind::Whitespace),
+            (Span { start: 27, end: 29 }, TokenKind::Keyword),
+        ]
+    );
+}
+
+#[test]
+fn literals_and_operators() {
+    assert_eq!(
+        classify(r#"f("a\"b", 'c')**0x1f"#),
+        vec![
+            ("f", TokenKind::Identifier),
+            ("(", TokenKind::Punctuation),
+            (r#""a\"b""#, TokenKind::String),
+            (",", TokenKind::Punctuation),
+            (" ", TokenKind::Whitespace),
+            ("'c'", TokenKind::Char),
+            (")", TokenKind::Punctuation),
+            ("**", TokenKind::Operator),
+            ("0x1f", TokenKind::Number),
+        ]
+    );
+}
+
+#[test]
+fn number_suffixes() {
+    assert_eq!(
+        classify("255u8 1i9"),
+        vec![
+            ("255u8", TokenKind::Number),
+            (" ", TokenKind::Whitespace),
+            ("1", TokenKind::Number),
+            ("i9", TokenKind::Identifier),
+        ]
+    );
+}
+
+#[test]
+fn identifier_with_keyword_prefix() {
+    assert_eq!(
+        classify("variable iffy"),
+        vec![
+            ("variable", TokenKind::Identifier),
+            (" ", TokenKind::Whitespace),
+            ("iffy", TokenKind::Identifier),
+        ]
+    );
+}
+
+#[test]
+fn unknown_characters() {
+    assert_eq!(
+        classify("@\"ab"),
+        vec![
+            ("@", TokenKind::Unknown),
+            ("\"", TokenKind::Unknown),
+            ("ab", TokenKind::Identifier),
+        ]
+    );
+}
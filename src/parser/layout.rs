@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod tests;
+
+use super::{ParseError, ParseErrorKind, TokenKind, context, highlight_tokens};
+
+/// Source rewritten by the off-side rule: a line indented deeper than the one
+/// before opens a block and a dedent closes every block it leaves, so the
+/// brace grammar parses indented blocks unchanged.
+///
+/// Blank lines, lines starting with a line comment, lines holding only
+/// comments and lines starting inside a block comment don't affect the
+/// layout.
+/// Continuation lines of a statement must not be indented, as any indent
+/// opens a block, unless the line before ends in a line continuation.
+pub(super) struct Layout {
+    pub(super) source: String,
+    // Offsets of the inserted braces in `source`, in increasing order.
+    inserted: Vec<usize>,
+}
+
+impl Layout {
+    pub(super) fn new(source: &str) -> Result<Self, ParseError> {
+        let mut layout = Layout {
+            source: String::with_capacity(source.len()),
+            inserted: Vec::new(),
+        };
+        let mut levels = vec![0];
+        let mut line_start = 0;
+        let mut continued = false;
+        // Tokens are only used to find the comments, which may span lines.
+        let tokens = highlight_tokens(source);
+        let mut token = 0;
+
+        for line in source.split_inclusive('\n') {
+            let content = line.trim_start_matches([' ', '\t']);
+            let indent = line.len() - content.len();
+            layout.source.push_str(&line[..indent]);
+
+            while tokens[token].0.end <= line_start {
+                token += 1;
+            }
+            let (span, kind) = tokens[token];
+            let in_comment = kind == TokenKind::Comment && span.start < line_start;
+
+            // A line holding nothing but comments, like `/* note */`, counts as
+            // blank too.
+            let line_end = line_start + line.len();
+            let blank = continued
+                || in_comment
+                || content.starts_with("//")
+                || content.starts_with('#')
+                || tokens[token..]
+                    .iter()
+                    .take_while(|(span, _)| span.start < line_end)
+                    .all(|(_, kind)| matches!(kind, TokenKind::Whitespace | TokenKind::Comment));
+            if !blank {
+                if indent > levels[levels.len() - 1] {
+                    levels.push(indent);
+                    layout.insert('{');
+                }
+
+                while indent < levels[levels.len() - 1] {
+                    levels.pop();
+                    layout.insert('}');
+                }
+
+                if indent != levels[levels.len() - 1] {
+                    return Err(ParseError {
+                        kind: ParseErrorKind::Syntax,
+                        offset: line_start + indent,
+                        message: "dedent doesn't match any outer indentation level".to_string(),
+                        expected: Vec::new(),
+                        context: Vec::new(),
+                    });
+                }
+            }
+
+            layout.source.push_str(content);
+            line_start += line.len();
+            continued = context::line_continuations()
+                && line.trim_end_matches(['\n', '\r']).ends_with('\\');
+        }
+
+        for _ in 1..levels.len() {
+            layout.insert('}');
+        }
+
+        Ok(layout)
+    }
+
+    fn insert(&mut self, brace: char) {
+        self.inserted.push(self.source.len());
+        self.source.push(brace);
+    }
+
+    /// Maps the offset of `error` in the rewritten source back to the original.
+    pub(super) fn restore(&self, mut error: ParseError) -> ParseError {
+        error.offset -= self.inserted.partition_point(|idx| *idx < error.offset);
+        error
+    }
+}
@@ -0,0 +1,49 @@
+use super::*;
+
+#[test]
+fn layout_braces() {
+    let layout = Layout::new("a\n  b\n\n  // note\n    c\nd\n  e").unwrap();
+    assert_eq!(layout.source, "a\n  {b\n\n  // note\n    {c\n}}d\n  {e}");
+}
+
+#[test]
+fn layout_multi_line_comment() {
+    let source = "x;\n/* a\n   b */\nz;";
+    assert_eq!(Layout::new(source).unwrap().source, source);
+
+    let source = "a\n  b; /* c\n      d */\n  e";
+    assert_eq!(
+        Layout::new(source).unwrap().source,
+        "a\n  {b; /* c\n      d */\n  e}"
+    );
+}
+
+#[test]
+fn layout_inconsistent_dedent() {
+    assert_eq!(
+        Layout::new("a\n    b\n  c\n").err(),
+        Some(ParseError {
+            kind: ParseErrorKind::Syntax,
+            offset: 10,
+            message: "dedent doesn't match any outer indentation level".to_string(),
+            expected: Vec::new(),
+            context: Vec::new(),
+        })
+    );
+}
+
+#[test]
+fn layout_restores_offsets() {
+    let layout = Layout::new("a\n  b\nc").unwrap();
+    let error = |offset| ParseError {
+        kind: ParseErrorKind::Syntax,
+        offset,
+        message: String::new(),
+        expected: Vec::new(),
+        context: Vec::new(),
+    };
+
+    // `c` is at offset 6 in the original and 8 after the inserted braces.
+    assert_eq!(layout.restore(error(8)), error(6));
+    assert_eq!(layout.restore(error(1)), error(1));
+}
@@ -1,15 +1,430 @@
 #[cfg(test)]
 mod tests;
 
+use std::{
+    fmt::{self, Display},
+    ops::{Bound, RangeBounds},
+};
+
+#[cfg(feature = "bigint")]
+use crate::bigint::BigInt;
+use crate::ranges::IntWidth;
+use crate::span::Span;
+
 mod ast;
+mod context;
+mod highlight;
+mod layout;
+#[cfg(feature = "profile")]
+mod profile;
+mod reparse;
+
+pub use highlight::{TokenKind, highlight_tokens};
+#[cfg(feature = "profile")]
+pub use profile::{RuleProfile, profile_report, reset_profile};
+pub use reparse::reparse;
+
+use context::{Memoized, Nested};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    Syntax,
+    RecursionLimit,
+    /// The `ParseOptions` contradict each other.
+    InvalidOptions,
+}
 
-#[derive(Debug, PartialEq, Eq)]
-struct Result<'a, T> {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// Byte offset into the source where parsing failed.
+    pub offset: usize,
+    pub message: String,
+    /// What `choice` alternatives tried at `offset` expected to find there.
+    pub expected: Vec<&'static str>,
+    /// Grammar rules, outermost first, that were being parsed when the error
+    /// occurred. See `Parser::with_context`.
+    pub context: Vec<&'static str>,
+}
+
+impl ParseError {
+    // `source` is the remaining input at the point of failure.
+    fn syntax(source: &str) -> Self {
+        let mut message = match source.chars().next() {
+            Some(ch) => format!(
+                "unexpected `{}`",
+                crate::ast::escape_identifier(ch.encode_utf8(&mut [0; 4]))
+            ),
+            None => "unexpected end of input".to_string(),
+        };
+
+        let offset = context::offset(source);
+        let expected = context::expected_at(offset);
+        match expected.as_slice() {
+            [] => (),
+            [name] => message.push_str(&format!(", expected {name}")),
+            names => message.push_str(&format!(", expected one of: {}", names.join(", "))),
+        }
+
+        Self {
+            kind: ParseErrorKind::Syntax,
+            offset,
+            message,
+            expected,
+            context: Vec::new(),
+        }
+    }
+
+    // `source` starts at the second comparison operator.
+    fn chained_comparison(source: &str) -> Self {
+        Self {
+            kind: ParseErrorKind::Syntax,
+            offset: context::offset(source),
+            message: "comparison operators can't be chained".to_string(),
+            expected: Vec::new(),
+            context: Vec::new(),
+        }
+    }
+
+    // `source` starts at the identifier.
+    fn identifier_too_long(source: &str, limit: usize) -> Self {
+        Self {
+            kind: ParseErrorKind::Syntax,
+            offset: context::offset(source),
+            message: format!("identifier is longer than the limit of {limit} characters"),
+            expected: Vec::new(),
+            context: Vec::new(),
+        }
+    }
+
+    fn invalid_options(message: &str) -> Self {
+        Self {
+            kind: ParseErrorKind::InvalidOptions,
+            offset: 0,
+            message: message.to_string(),
+            expected: Vec::new(),
+            context: Vec::new(),
+        }
+    }
+
+    fn recursion_limit(offset: usize, limit: usize) -> Self {
+        Self {
+            kind: ParseErrorKind::RecursionLimit,
+            offset,
+            message: format!("nesting exceeds the recursion limit of {limit}"),
+            expected: Vec::new(),
+            context: Vec::new(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for rule in &self.context {
+            write!(f, "while parsing {rule} → ")?;
+        }
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberConfig {
+    pub decimal: bool,
+    pub hex: bool,
+    pub octal: bool,
+    pub binary: bool,
+}
+
+impl Default for NumberConfig {
+    fn default() -> Self {
+        Self {
+            decimal: true,
+            hex: true,
+            octal: false,
+            binary: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommentStyle {
+    pub double_slash: bool,
+    pub hash: bool,
+    pub block: bool,
+}
+
+impl Default for CommentStyle {
+    fn default() -> Self {
+        Self {
+            double_slash: true,
+            hash: false,
+            block: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    pub recursion_limit: usize,
+    pub number: NumberConfig,
+    pub comments: CommentStyle,
+    pub recover_to_newline: bool,
+    pub memoize: bool,
+    /// Lets indentation delimit blocks in `parse_program`, in addition to
+    /// braces.
+    pub indent_blocks: bool,
+    /// Rejects `a == b == c`, which compares the result of `a == b` rather
+    /// than all three operands. Parenthesised operands are still accepted.
+    /// The language has no relational or logical operators, so `==` and `!=`
+    /// are the only comparisons that can chain.
+    pub reject_chained_comparisons: bool,
+    /// Treats a backslash right before a line break as whitespace, joining
+    /// the two lines. With `indent_blocks`, the joined line doesn't take part
+    /// in the layout.
+    pub line_continuations: bool,
+    /// Longest identifier accepted, in characters. Unlimited when `None`.
+    pub max_identifier_len: Option<usize>,
+    /// Parses `//` as integer division. As `//` starts a comment with the
+    /// default `CommentStyle`, `comments.double_slash` has to be off, and
+    /// parsing fails with `ParseErrorKind::InvalidOptions` otherwise.
+    pub int_division: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            recursion_limit: 256,
+            number: NumberConfig::default(),
+            comments: CommentStyle::default(),
+            recover_to_newline: false,
+            memoize: false,
+            indent_blocks: false,
+            reject_chained_comparisons: false,
+            line_continuations: false,
+            max_identifier_len: None,
+            int_division: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    pub fn parse_program(&self, source: &str) -> std::result::Result<crate::ast::Node, ParseError> {
+        if self.indent_blocks {
+            let layout = self.run(|| layout::Layout::new(source))?;
+            return self
+                .run(|| ast::program.parse_to_completion(&layout.source))
+                .map_err(|error| layout.restore(error));
+        }
+
+        self.run(|| ast::program.parse_to_completion(source))
+    }
+
+    pub fn parse_expression(
+        &self,
+        source: &str,
+    ) -> std::result::Result<crate::ast::Node, ParseError> {
+        self.run(|| ignored.and(ast::expression).parse_to_completion(source))
+    }
+
+    pub fn statements<'a>(
+        &self,
+        source: &'a str,
+    ) -> impl Iterator<Item = std::result::Result<crate::ast::Node, ParseError>> + use<'a> {
+        Statements {
+            options: self.clone(),
+            input: source,
+            source: Some(source),
+        }
+    }
+
+    /// Parses every top level item it can. An item that fails to parse is
+    /// replaced by `Node::Error` and its error is collected, after which
+    /// parsing resumes past the broken item.
+    pub fn parse_resilient(&self, source: &str) -> (crate::ast::Node, Vec<ParseError>) {
+        let mut items = Vec::new();
+        let mut errors = Vec::new();
+        let mut rest = source;
+
+        loop {
+            rest = context::with_options(self, || ignored.parse(rest))
+                .0
+                .map_or(rest, |res| res.source);
+            if rest.is_empty() {
+                break;
+            }
+
+            match self.run(|| {
+                context::with_input(source, || {
+                    ast::statement
+                        .parse(rest)
+                        .ok_or_else(|| ParseError::syntax(rest))
+                })
+            }) {
+                Ok(res) => {
+                    items.push(res.value);
+                    rest = res.source;
+                }
+                Err(error) => {
+                    items.push(crate::ast::Node::Error);
+                    errors.push(error);
+                    rest = skip_item(rest);
+                }
+            }
+        }
+
+        (crate::ast::Node::Block(items), errors)
+    }
+
+    fn run<T>(
+        &self,
+        f: impl FnOnce() -> std::result::Result<T, ParseError>,
+    ) -> std::result::Result<T, ParseError> {
+        if self.int_division && self.comments.double_slash {
+            return Err(ParseError::invalid_options(
+                "`//` can't be both integer division and a comment",
+            ));
+        }
+
+        match context::with_options(self, f) {
+            (_, Some(error)) => Err(error),
+            (res, None) => res,
+        }
+    }
+}
+
+pub fn parse_program(source: &str) -> std::result::Result<crate::ast::Node, ParseError> {
+    ParseOptions::default().parse_program(source)
+}
+
+pub fn parse_expression(source: &str) -> std::result::Result<crate::ast::Node, ParseError> {
+    ParseOptions::default().parse_expression(source)
+}
+
+pub fn parse_resilient(source: &str) -> (crate::ast::Node, Vec<ParseError>) {
+    ParseOptions::default().parse_resilient(source)
+}
+
+/// Runs `parser` on `source`, returning its result along with the errors it
+/// recovered from through `recover_with`, in the order they were recorded.
+/// Diagnostics of alternatives that were backtracked out of are kept as well.
+pub fn with_diagnostics<'a, P>(
+    parser: P,
     source: &'a str,
-    value: T,
+) -> (Option<Result<'a, P::Output>>, Vec<ParseError>)
+where
+    P: Parser<'a>,
+{
+    let ((res, mut diagnostics), error) = context::with_options(&ParseOptions::default(), || {
+        context::with_input(source, || {
+            let res = parser.parse(source);
+            (res, context::take_diagnostics())
+        })
+    });
+
+    diagnostics.extend(error);
+    (res, diagnostics)
 }
 
-trait Parser<'a> {
+// Skips past a broken item: up to the first `;` outside of braces, or the `}`
+// closing the braces opened within the item.
+fn skip_item(source: &str) -> &str {
+    let mut depth = 0;
+    for (idx, ch) in source.char_indices() {
+        match ch {
+            '{' => depth += 1,
+            '}' if depth > 1 => depth -= 1,
+            '}' => return &source[idx + 1..],
+            ';' if depth == 0 => return &source[idx + 1..],
+            _ => (),
+        }
+    }
+
+    ""
+}
+
+pub fn statements(
+    source: &str,
+) -> impl Iterator<Item = std::result::Result<crate::ast::Node, ParseError>> + '_ {
+    ParseOptions::default().statements(source)
+}
+
+struct Statements<'a> {
+    options: ParseOptions,
+    input: &'a str,
+    source: Option<&'a str>,
+}
+
+impl Iterator for Statements<'_> {
+    type Item = std::result::Result<crate::ast::Node, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.source?;
+        let source = context::with_options(&self.options, || ignored.parse(rest))
+            .0?
+            .source;
+        if source.is_empty() {
+            self.source = None;
+            return None;
+        }
+
+        let input = self.input;
+        match self.options.run(|| {
+            context::with_input(input, || {
+                ast::statement
+                    .parse(source)
+                    .ok_or_else(|| ParseError::syntax(source))
+            })
+        }) {
+            Ok(res) => {
+                self.source = Some(res.source);
+                Some(Ok(res.value))
+            }
+            Err(error) => {
+                self.source = if self.options.recover_to_newline {
+                    source.split_once('\n').map(|(_, rest)| rest)
+                } else {
+                    None
+                };
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Result<'a, T> {
+    pub source: &'a str,
+    pub value: T,
+}
+
+/// A position within an input, to parse from with `Parser::iter_parse`. It is
+/// only a reference to the input and an offset, so it can be kept around and
+/// parsed from again, for example to backtrack after a speculative parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseState<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+impl<'a> ParseState<'a> {
+    /// State at the start of `input`.
+    pub fn new(input: &'a str) -> Self {
+        ParseState { input, offset: 0 }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The part of the input that is left to parse.
+    pub fn remaining(&self) -> &'a str {
+        &self.input[self.offset..]
+    }
+}
+
+pub trait Parser<'a> {
     type Output;
 
     fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>>;
@@ -22,6 +437,112 @@ trait Parser<'a> {
         Choice(self, other)
     }
 
+    fn or_else<P, F>(self, function: F) -> OrElse<Self, F>
+    where
+        Self: Sized,
+        P: Parser<'a, Output = Self::Output>,
+        F: Fn() -> P,
+    {
+        OrElse {
+            parser: self,
+            function,
+        }
+    }
+
+    fn opt(self) -> Maybe<Self>
+    where
+        Self: Sized,
+    {
+        Maybe::new(self)
+    }
+
+    fn cut(self) -> Cut<Self>
+    where
+        Self: Sized,
+    {
+        Cut(self)
+    }
+
+    /// Produces `default` without consuming anything when `self` fails, and
+    /// records the failure as a diagnostic, see `with_diagnostics`.
+    fn recover_with<T>(self, default: T) -> RecoverWith<Self, T>
+    where
+        Self: Sized + Parser<'a, Output = T>,
+        T: Clone,
+    {
+        RecoverWith {
+            parser: self,
+            default,
+        }
+    }
+
+    /// Names the grammar rule `self` parses. When `self` fails with an error
+    /// that aborts the parse, `name` is added to the error's context, so
+    /// nested rules build up a trail of what was being parsed.
+    fn with_context(self, name: &'static str) -> WithContext<Self>
+    where
+        Self: Sized,
+    {
+        WithContext { parser: self, name }
+    }
+
+    fn repeat<R>(self, range: R) -> Repeat<Self>
+    where
+        Self: Sized,
+        R: RangeBounds<usize>,
+    {
+        Repeat::new(self, range)
+    }
+
+    fn collect_into<C>(self) -> impl Parser<'a, Output = C>
+    where
+        Self: Sized,
+        C: FromIterator<Self::Output>,
+    {
+        let parser = self.repeat(..);
+        move |source: &'a str| {
+            let res = parser.parse(source)?;
+            Some(Result {
+                source: res.source,
+                value: res.value.into_iter().collect(),
+            })
+        }
+    }
+
+    /// Maps every item of the output to an iterable with `function`, and
+    /// concatenates the results. Flattens repeated groups with an identity
+    /// `function`.
+    fn flat_map<F, I>(self, function: F) -> impl Parser<'a, Output = Vec<I::Item>>
+    where
+        Self: Sized,
+        Self::Output: IntoIterator,
+        F: Fn(<Self::Output as IntoIterator>::Item) -> I,
+        I: IntoIterator,
+    {
+        move |source: &'a str| {
+            let res = self.parse(source)?;
+            Some(Result {
+                source: res.source,
+                value: res.value.into_iter().flat_map(&function).collect(),
+            })
+        }
+    }
+
+    fn many1_sep<S>(self, separator: S) -> impl Parser<'a, Output = Vec<Self::Output>>
+    where
+        Self: Sized,
+        S: Parser<'a>,
+    {
+        sep_by1(self, separator)
+    }
+
+    fn between_optional_whitespace(self) -> impl Parser<'a, Output = Self::Output>
+    where
+        Self: Sized,
+    {
+        ws(self)
+    }
+
     fn bind<U, F, O>(self, function: F) -> Bind<Self, F>
     where
         Self: Sized,
@@ -32,188 +553,751 @@ trait Parser<'a> {
             parser: self,
             function,
         }
-    }
+    }
+
+    fn then_with<F, O>(self, function: F) -> Bind<Self, F>
+    where
+        Self: Sized,
+        O: Parser<'a>,
+        F: Fn(Self::Output) -> O,
+    {
+        self.bind(function)
+    }
+
+    fn inspect<F>(self, function: F) -> Inspect<Self, F>
+    where
+        Self: Sized,
+        F: Fn(&Self::Output),
+    {
+        Inspect {
+            parser: self,
+            function,
+        }
+    }
+
+    fn and<U, P>(self, other: P) -> And<Self, P>
+    where
+        Self: Sized,
+        P: Parser<'a, Output = U>,
+    {
+        And(self, other)
+    }
+
+    fn pair<U, P>(self, other: P) -> impl Parser<'a, Output = (Self::Output, U)>
+    where
+        Self: Sized,
+        P: Parser<'a, Output = U>,
+    {
+        move |source: &'a str| {
+            let left = self.parse(source)?;
+            let right = other.parse(left.source)?;
+            Some(Result {
+                source: right.source,
+                value: (left.value, right.value),
+            })
+        }
+    }
+
+    fn value<U>(self, value: U) -> impl Parser<'a, Output = U>
+    where
+        Self: Sized,
+        U: Clone,
+    {
+        self.and(Constant::new(value))
+    }
+
+    fn map<U, F>(self, function: F) -> impl Parser<'a, Output = U>
+    where
+        Self: Sized,
+        U: Clone,
+        F: Fn(Self::Output) -> U + 'a,
+    {
+        self.bind(move |val| Constant::new(function(val)))
+    }
+
+    fn map_with_span<U, F>(self, function: F) -> impl Parser<'a, Output = U>
+    where
+        Self: Sized,
+        U: Clone,
+        F: Fn(Self::Output, Span) -> U + 'a,
+    {
+        Spanned::new(self).map(move |(span, val)| function(val, span))
+    }
+
+    fn complete(self) -> impl Parser<'a, Output = Self::Output>
+    where
+        Self: Sized,
+    {
+        move |source: &'a str| self.parse(source).filter(|res| res.source.is_empty())
+    }
+
+    /// Parses from `state`, returning the output along with the state after
+    /// it. Offsets in errors are relative to the start of the whole input,
+    /// not to `state`.
+    fn iter_parse(&self, state: ParseState<'a>) -> Option<(Self::Output, ParseState<'a>)> {
+        context::with_input(state.input, || {
+            let res = self.parse(state.remaining())?;
+            let next = ParseState {
+                input: state.input,
+                offset: state.input.len() - res.source.len(),
+            };
+            Some((res.value, next))
+        })
+    }
+
+    fn parse_to_completion(
+        &self,
+        source: &'a str,
+    ) -> std::result::Result<Self::Output, ParseError> {
+        context::with_input(source, || match self.parse(source) {
+            Some(Result { source: "", value }) => Ok(value),
+            Some(res) => Err(ParseError::syntax(res.source)),
+            None => Err(ParseError::syntax(source)),
+        })
+    }
+}
+
+impl<'a, F, T> Parser<'a> for F
+where
+    F: Fn(&'a str) -> Option<Result<'a, T>>,
+{
+    type Output = T;
+
+    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
+        self(source)
+    }
+}
+
+pub struct Constant<T: Clone>(T);
+
+impl<T: Clone> Constant<T> {
+    pub fn new(value: T) -> Self {
+        Constant(value)
+    }
+}
+
+impl<'a, T> Parser<'a> for Constant<T>
+where
+    T: Clone,
+{
+    type Output = T;
+
+    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
+        Some(Result {
+            source,
+            value: self.0.clone(),
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Choice<P1, P2>(P1, P2);
+
+impl<'a, T, P1, P2> Parser<'a> for Choice<P1, P2>
+where
+    P1: Parser<'a, Output = T>,
+    P2: Parser<'a, Output = T>,
+{
+    type Output = T;
+
+    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
+        let res = self.0.parse(source);
+        if res.is_some() {
+            res
+        } else {
+            self.1.parse(source)
+        }
+    }
+}
+
+pub struct OrElse<P, F> {
+    parser: P,
+    function: F,
+}
+
+impl<'a, T, P, O, F> Parser<'a> for OrElse<P, F>
+where
+    P: Parser<'a, Output = T>,
+    O: Parser<'a, Output = T>,
+    F: Fn() -> O,
+{
+    type Output = T;
+
+    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
+        let res = self.parser.parse(source);
+        if res.is_some() {
+            res
+        } else {
+            (self.function)().parse(source)
+        }
+    }
+}
+
+pub struct Repeat<P> {
+    parser: P,
+    bounds: (Bound<usize>, Bound<usize>),
+}
+
+impl<'a, P> Repeat<P>
+where
+    P: Parser<'a>,
+{
+    fn new<R: RangeBounds<usize>>(parser: P, range: R) -> Self {
+        Repeat {
+            parser,
+            bounds: (range.start_bound().cloned(), range.end_bound().cloned()),
+        }
+    }
+
+    fn below_max(&self, count: usize) -> bool {
+        match self.bounds.1 {
+            Bound::Included(max) => count < max,
+            Bound::Excluded(max) => count + 1 < max,
+            Bound::Unbounded => true,
+        }
+    }
+}
+
+impl<'a, T, P> Parser<'a> for Repeat<P>
+where
+    P: Parser<'a, Output = T>,
+{
+    type Output = Vec<T>;
+
+    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
+        let mut result = Vec::new();
+        let mut remaining = source;
+
+        while self.below_max(result.len()) {
+            let Some(res) = self.parser.parse(remaining) else {
+                break;
+            };
+
+            result.push(res.value);
+            remaining = res.source;
+        }
+
+        if !self.bounds.contains(&result.len()) {
+            return None;
+        }
+
+        Some(Result {
+            source: remaining,
+            value: result,
+        })
+    }
+}
+
+pub struct ZeroOrMore<P>(Repeat<P>);
+
+impl<'a, P> ZeroOrMore<P>
+where
+    P: Parser<'a>,
+{
+    pub fn new(parser: P) -> Self {
+        ZeroOrMore(parser.repeat(..))
+    }
+}
+
+impl<'a, T, P> Parser<'a> for ZeroOrMore<P>
+where
+    P: Parser<'a, Output = T>,
+{
+    type Output = Vec<T>;
+
+    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
+        self.0.parse(source)
+    }
+}
+
+pub struct Bind<P, F> {
+    parser: P,
+    function: F,
+}
+
+impl<'a, P, O, F, T, U> Parser<'a> for Bind<P, F>
+where
+    P: Parser<'a, Output = T>,
+    O: Parser<'a, Output = U>,
+    F: Fn(T) -> O,
+{
+    type Output = U;
+
+    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
+        let res = self.parser.parse(source)?;
+        let p = (self.function)(res.value);
+        p.parse(res.source)
+    }
+}
+
+pub struct Inspect<P, F> {
+    parser: P,
+    function: F,
+}
+
+impl<'a, P, F, T> Parser<'a> for Inspect<P, F>
+where
+    P: Parser<'a, Output = T>,
+    F: Fn(&T),
+{
+    type Output = T;
+
+    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
+        let res = self.parser.parse(source)?;
+        (self.function)(&res.value);
+        Some(res)
+    }
+}
+
+pub struct And<P1, P2>(P1, P2);
+
+impl<'a, T, U, P1, P2> Parser<'a> for And<P1, P2>
+where
+    P1: Parser<'a, Output = T>,
+    P2: Parser<'a, Output = U>,
+{
+    type Output = U;
+
+    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
+        let res = self.0.parse(source)?;
+        self.1.parse(res.source)
+    }
+}
+
+/// Commits to a parser: once the grammar reaches it, a failure is reported as
+/// a syntax error at the current position instead of letting an enclosing
+/// alternative try another branch.
+pub struct Cut<P>(P);
+
+impl<'a, P> Parser<'a> for Cut<P>
+where
+    P: Parser<'a>,
+{
+    type Output = P::Output;
+
+    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
+        let res = self.0.parse(source);
+        if res.is_none() {
+            context::fail(ParseError::syntax(source));
+        }
+
+        res
+    }
+}
+
+pub struct RecoverWith<P, T> {
+    parser: P,
+    default: T,
+}
+
+impl<'a, T, P> Parser<'a> for RecoverWith<P, T>
+where
+    P: Parser<'a, Output = T>,
+    T: Clone,
+{
+    type Output = T;
+
+    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
+        self.parser.parse(source).or_else(|| {
+            context::report(ParseError::syntax(source));
+            Some(Result {
+                source,
+                value: self.default.clone(),
+            })
+        })
+    }
+}
+
+pub struct WithContext<P> {
+    parser: P,
+    name: &'static str,
+}
+
+impl<'a, P> Parser<'a> for WithContext<P>
+where
+    P: Parser<'a>,
+{
+    type Output = P::Output;
+
+    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
+        // An error recorded before `self` ran belongs to another rule.
+        let failed = context::failed();
+        let res = self.parser.parse(source);
+        if res.is_none() && !failed {
+            context::add_context(self.name);
+        }
+
+        res
+    }
+}
+
+pub struct Maybe<P>(P);
+
+impl<'a, P> Maybe<P>
+where
+    P: Parser<'a>,
+{
+    pub fn new(parser: P) -> Self {
+        Maybe(parser)
+    }
+}
+
+impl<'a, T, P> Parser<'a> for Maybe<P>
+where
+    P: Parser<'a, Output = T>,
+{
+    type Output = Option<T>;
+
+    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
+        let res = self.0.parse(source);
+        if let Some(res) = res {
+            Some(Result {
+                source: res.source,
+                value: Some(res.value),
+            })
+        } else {
+            Some(Result {
+                source,
+                value: None,
+            })
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+pub fn either<'a, P1, P2, T, U>(left: P1, right: P2) -> impl Parser<'a, Output = Either<T, U>>
+where
+    P1: Parser<'a, Output = T>,
+    P2: Parser<'a, Output = U>,
+{
+    move |source: &'a str| {
+        if let Some(res) = left.parse(source) {
+            return Some(Result {
+                source: res.source,
+                value: Either::Left(res.value),
+            });
+        }
 
-    fn and<U, P>(self, other: P) -> And<Self, P>
-    where
-        Self: Sized,
-        P: Parser<'a, Output = U>,
-    {
-        And(self, other)
+        let res = right.parse(source)?;
+        Some(Result {
+            source: res.source,
+            value: Either::Right(res.value),
+        })
     }
+}
 
-    fn map<U, F>(self, function: F) -> impl Parser<'a, Output = U>
-    where
-        Self: Sized,
-        U: Clone,
-        F: Fn(Self::Output) -> U + 'a,
-    {
-        self.bind(move |val| Constant::new(function(val)))
-    }
+pub struct Spanned<P>(P);
 
-    #[allow(clippy::result_unit_err)]
-    fn parse_to_completion(&self, source: &'a str) -> std::result::Result<Self::Output, ()> {
-        match self.parse(source) {
-            None => Err(()),
-            Some(Result {
-                source: "",
-                value: _,
-            }) => Err(()),
-            Some(Result { source: _, value }) => Ok(value),
-        }
+impl<'a, P> Spanned<P>
+where
+    P: Parser<'a>,
+{
+    pub fn new(parser: P) -> Self {
+        Spanned(parser)
     }
 }
 
-impl<'a, F, T> Parser<'a> for F
+impl<'a, T, P> Parser<'a> for Spanned<P>
 where
-    F: Fn(&'a str) -> Option<Result<'a, T>>,
+    P: Parser<'a, Output = T>,
 {
-    type Output = T;
+    type Output = (Span, T);
 
     fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
-        self(source)
+        context::with_input(source, || {
+            let res = self.0.parse(source)?;
+            let consumed = &source[..source.len() - res.source.len()];
+
+            let start = context::offset(source);
+            let span = Span {
+                start,
+                end: start + consumed.trim_end().len(),
+            };
+
+            Some(Result {
+                source: res.source,
+                value: (span, res.value),
+            })
+        })
     }
 }
 
-struct Constant<T: Clone>(T);
+/// Tries `branches` in order, each named by what it parses. When they all
+/// fail, their names are recorded as expected at the current position, so
+/// that a syntax error reported there lists them.
+pub fn choice<'a, T>(
+    branches: Vec<(&'static str, Box<dyn Parser<'a, Output = T> + 'a>)>,
+) -> impl Parser<'a, Output = T> {
+    move |source: &'a str| {
+        let res = branches.iter().find_map(|(_, branch)| branch.parse(source));
+        if res.is_none() {
+            let names = branches.iter().map(|(name, _)| *name);
+            context::expect(context::offset(source), names);
+        }
 
-impl<T: Clone> Constant<T> {
-    fn new(value: T) -> Self {
-        Constant(value)
+        res
     }
 }
 
-impl<'a, T> Parser<'a> for Constant<T>
+/// Runs `parser` on at most the next `len` bytes of the source. Fails if the
+/// parser fails within the window, or if the match would continue past it,
+/// such as a number whose digits run on. Trailing whitespace consumed past the
+/// window doesn't count.
+pub fn within<'a, P>(len: usize, parser: P) -> impl Parser<'a, Output = P::Output>
 where
-    T: Clone,
+    P: Parser<'a>,
 {
-    type Output = T;
+    move |source: &'a str| {
+        let window = if len >= source.len() {
+            source
+        } else {
+            source.get(..len)?
+        };
+
+        let res = parser.parse(window)?;
+        if res.source.is_empty() && window.len() < source.len() {
+            let full = parser.parse(source)?;
+            let consumed = &source[..source.len() - full.source.len()];
+            if consumed.trim_end().len() > window.len() {
+                return None;
+            }
+        }
 
-    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
         Some(Result {
-            source,
-            value: self.0.clone(),
+            source: &source[window.len() - res.source.len()..],
+            value: res.value,
         })
     }
 }
 
-struct Choice<P1, P2>(P1, P2);
+/// Returns the next character of `source` without consuming anything.
+pub fn peek_char(source: &str) -> Option<char> {
+    source.chars().next()
+}
 
-impl<'a, T, P1, P2> Parser<'a> for Choice<P1, P2>
+/// Dispatches on the next character: runs `matching` if it satisfies
+/// `predicate` and `otherwise` if it doesn't, or if the input is empty. Only
+/// the chosen parser runs, so a cheap check can stand in for backtracking.
+pub fn branch<'a, F, P1, P2, T>(
+    predicate: F,
+    matching: P1,
+    otherwise: P2,
+) -> impl Parser<'a, Output = T>
 where
+    F: Fn(char) -> bool,
     P1: Parser<'a, Output = T>,
     P2: Parser<'a, Output = T>,
 {
-    type Output = T;
-
-    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
-        let res = self.0.parse(source);
-        if res.is_some() {
-            res
-        } else {
-            self.1.parse(source)
-        }
+    move |source: &'a str| match peek_char(source) {
+        Some(ch) if predicate(ch) => matching.parse(source),
+        _ => otherwise.parse(source),
     }
 }
 
-struct ZeroOrMore<P>(P);
+/// Runs `parser` with any ignored content (whitespace and comments) before and
+/// after it skipped.
+pub fn ws<'a, P>(parser: P) -> impl Parser<'a, Output = P::Output>
+where
+    P: Parser<'a>,
+{
+    move |source: &'a str| {
+        let source = ignored.parse(source).map_or(source, |res| res.source);
+        let res = parser.parse(source)?;
+        let rest = ignored
+            .parse(res.source)
+            .map_or(res.source, |res| res.source);
+        Some(Result {
+            source: rest,
+            value: res.value,
+        })
+    }
+}
 
-impl<'a, P> ZeroOrMore<P>
+/// Returns the slice of the source matched by `parser` instead of its output.
+/// Trailing whitespace consumed by the parser is left out, as in `Spanned`.
+pub fn recognize<'a, P>(parser: P) -> impl Parser<'a, Output = &'a str>
 where
     P: Parser<'a>,
 {
-    fn new(parser: P) -> Self {
-        ZeroOrMore(parser)
+    move |source: &'a str| {
+        let res = parser.parse(source)?;
+        let consumed = &source[..source.len() - res.source.len()];
+        Some(Result {
+            source: res.source,
+            value: consumed.trim_end(),
+        })
     }
 }
 
-impl<'a, T, P> Parser<'a> for ZeroOrMore<P>
+/// Records the time spent in `parser` under `rule` when the `profile` feature
+/// is enabled, see `profile_report`. Without the feature, `parser` is returned
+/// as is.
+#[cfg(feature = "profile")]
+pub fn profile<'a, P>(rule: &'static str, parser: P) -> impl Parser<'a, Output = P::Output>
 where
-    P: Parser<'a, Output = T>,
+    P: Parser<'a>,
 {
-    type Output = Vec<T>;
+    profile::Profiled::new(rule, parser)
+}
 
-    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
-        let mut result = Vec::new();
+#[cfg(not(feature = "profile"))]
+pub fn profile<'a, P>(_rule: &'static str, parser: P) -> P
+where
+    P: Parser<'a>,
+{
+    parser
+}
+
+pub fn sep_by<'a, E, S, T>(element: E, separator: S) -> impl Parser<'a, Output = Vec<T>>
+where
+    E: Parser<'a, Output = T>,
+    S: Parser<'a>,
+{
+    move |source: &'a str| {
         let mut remaining = source;
+        let mut elements = Vec::new();
 
-        while let Some(res) = self.0.parse(remaining) {
-            result.push(res.value);
+        if let Some(res) = element.parse(remaining) {
+            elements.push(res.value);
             remaining = res.source;
+
+            while let Some(res) = separator
+                .parse(remaining)
+                .and_then(|sep| element.parse(sep.source))
+            {
+                elements.push(res.value);
+                remaining = res.source;
+            }
         }
 
         Some(Result {
             source: remaining,
-            value: result,
+            value: elements,
         })
     }
 }
 
-struct Bind<P, F> {
-    parser: P,
-    function: F,
-}
-
-impl<'a, P, O, F, T, U> Parser<'a> for Bind<P, F>
+/// Parses exactly `N` elements separated by `separator` into an array. Fails
+/// when there are fewer elements, or when another separated element follows.
+pub fn count_array<'a, const N: usize, E, S, T>(
+    element: E,
+    separator: S,
+) -> impl Parser<'a, Output = [T; N]>
 where
-    P: Parser<'a, Output = T>,
-    O: Parser<'a, Output = U>,
-    F: Fn(T) -> O,
+    E: Parser<'a, Output = T>,
+    S: Parser<'a>,
 {
-    type Output = U;
+    let next = move |source: &'a str, first: bool| {
+        let source = if first {
+            source
+        } else {
+            separator.parse(source)?.source
+        };
+        element.parse(source)
+    };
 
-    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
-        let res = self.parser.parse(source)?;
-        let p = (self.function)(res.value);
-        p.parse(res.source)
+    move |source: &'a str| {
+        let mut remaining = source;
+        let mut failed = false;
+        let elements: [Option<T>; N] = std::array::from_fn(|idx| {
+            if failed {
+                return None;
+            }
+
+            let res = next(remaining, idx == 0);
+            failed = res.is_none();
+            res.map(|res| {
+                remaining = res.source;
+                res.value
+            })
+        });
+
+        if failed || (N > 0 && next(remaining, false).is_some()) {
+            return None;
+        }
+
+        Some(Result {
+            source: remaining,
+            value: elements.map(|element| element.expect("every element was parsed")),
+        })
     }
 }
 
-struct And<P1, P2>(P1, P2);
+pub fn sep_by1<'a, E, S, T>(element: E, separator: S) -> impl Parser<'a, Output = Vec<T>>
+where
+    E: Parser<'a, Output = T>,
+    S: Parser<'a>,
+{
+    let parser = sep_by(element, separator);
+    move |source: &'a str| parser.parse(source).filter(|res| !res.value.is_empty())
+}
 
-impl<'a, T, U, P1, P2> Parser<'a> for And<P1, P2>
+/// Parses zero or more elements, each followed by `separator`, like statements
+/// terminated by `;`. Fails when an element is missing its separator.
+pub fn sep_end_by<'a, E, S, T>(element: E, separator: S) -> impl Parser<'a, Output = Vec<T>>
 where
-    P1: Parser<'a, Output = T>,
-    P2: Parser<'a, Output = U>,
+    E: Parser<'a, Output = T>,
+    S: Parser<'a>,
 {
-    type Output = U;
+    move |source: &'a str| {
+        let mut remaining = source;
+        let mut elements = Vec::new();
 
-    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
-        let res = self.0.parse(source)?;
-        self.1.parse(res.source)
+        while let Some(res) = element.parse(remaining) {
+            elements.push(res.value);
+            remaining = separator.parse(res.source)?.source;
+        }
+
+        Some(Result {
+            source: remaining,
+            value: elements,
+        })
     }
 }
 
-struct Maybe<P>(P);
-
-impl<'a, P> Maybe<P>
+/// Parses `first` and `second` with `separator` in between, keeping the
+/// outputs of `first` and `second`.
+pub fn separated_pair<'a, P1, S, P2>(
+    first: P1,
+    separator: S,
+    second: P2,
+) -> impl Parser<'a, Output = (P1::Output, P2::Output)>
 where
-    P: Parser<'a>,
+    P1: Parser<'a>,
+    S: Parser<'a>,
+    P2: Parser<'a>,
 {
-    fn new(parser: P) -> Self {
-        Maybe(parser)
-    }
+    first.pair(separator.and(second))
 }
 
-impl<'a, T, P> Parser<'a> for Maybe<P>
+pub fn delimited_list<'a, O, E, S, C, T>(
+    open: O,
+    element: E,
+    separator: S,
+    close: C,
+) -> impl Parser<'a, Output = Vec<(Span, T)>>
 where
-    P: Parser<'a, Output = T>,
+    O: Parser<'a>,
+    E: Parser<'a, Output = T>,
+    S: Parser<'a>,
+    C: Parser<'a>,
 {
-    type Output = Option<T>;
+    let elements = sep_by(Spanned::new(element), separator);
 
-    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
-        let res = self.0.parse(source);
-        if let Some(res) = res {
-            Some(Result {
-                source: res.source,
-                value: Some(res.value),
-            })
-        } else {
-            Some(Result {
-                source,
-                value: None,
-            })
-        }
+    move |source: &'a str| {
+        let remaining = open.parse(source)?.source;
+        let Result { source, value } = elements.parse(remaining)?;
+
+        let res = close.parse(source)?;
+        Some(Result {
+            source: res.source,
+            value,
+        })
     }
 }
 
@@ -243,26 +1327,27 @@ fn whitespace(source: &'_ str) -> Option<Result<'_, ()>> {
     }
 }
 
-fn single_line_comment(source: &'_ str) -> Option<Result<'_, ()>> {
-    if !source.starts_with("//") {
-        return None;
-    }
-
-    for (idx, ch) in source.char_indices().skip(2) {
-        if ch == '\n' {
-            return Some(Result {
-                source: &source[(idx + 1)..],
-                value: (),
-            });
-        }
-    }
+fn line_comment<'a>(prefix: &str, source: &'a str) -> Option<Result<'a, ()>> {
+    let rest = source.strip_prefix(prefix)?;
+    let rest = match rest.split_once('\n') {
+        Some((_, rest)) => rest,
+        None => "",
+    };
 
     Some(Result {
-        source: "",
+        source: rest,
         value: (),
     })
 }
 
+fn single_line_comment(source: &'_ str) -> Option<Result<'_, ()>> {
+    line_comment("//", source)
+}
+
+fn hash_comment(source: &'_ str) -> Option<Result<'_, ()>> {
+    line_comment("#", source)
+}
+
 fn multi_line_comment(source: &'_ str) -> Option<Result<'_, ()>> {
     if !source.starts_with("/*") {
         return None;
@@ -280,12 +1365,37 @@ fn multi_line_comment(source: &'_ str) -> Option<Result<'_, ()>> {
     None
 }
 
+type Comment = for<'a> fn(&'a str) -> Option<Result<'a, ()>>;
+
 fn comments(source: &'_ str) -> Option<Result<'_, ()>> {
-    single_line_comment.or(multi_line_comment).parse(source)
+    let style = context::comment_style();
+    let enabled: [(bool, Comment); 3] = [
+        (style.double_slash, single_line_comment),
+        (style.hash, hash_comment),
+        (style.block, multi_line_comment),
+    ];
+
+    enabled
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .find_map(|(_, comment)| comment(source))
+}
+
+fn line_continuation(source: &'_ str) -> Option<Result<'_, ()>> {
+    if !context::line_continuations() {
+        return None;
+    }
+
+    let rest = source.strip_prefix('\\')?;
+    let rest = rest.strip_prefix('\r').unwrap_or(rest);
+    Some(Result {
+        source: rest.strip_prefix('\n')?,
+        value: (),
+    })
 }
 
 fn ignored(source: &'_ str) -> Option<Result<'_, ()>> {
-    ZeroOrMore::new(whitespace.or(comments))
+    ZeroOrMore::new(whitespace.or(comments).or(line_continuation))
         .map(|_| ())
         .parse(source)
 }
@@ -328,7 +1438,7 @@ impl<'a> Parser<'a> for TokenBase<'a> {
 
         if ch.is_whitespace() {
             Some(Result {
-                source: &source[(idx + ch.len_utf8())..],
+                source: &source[(self.token.len() + idx + ch.len_utf8())..],
                 value: self.token,
             })
         } else {
@@ -337,38 +1447,60 @@ impl<'a> Parser<'a> for TokenBase<'a> {
     }
 }
 
+/// Matches `word` unless an identifier continues right after it, so `if` is
+/// found in `if (` and `if(`, but not in `iffy`. Skips whitespace and
+/// comments that follow.
+pub fn keyword<'a>(word: &'static str) -> impl Parser<'a, Output = &'a str> {
+    move |source: &'a str| {
+        let rest = source.strip_prefix(word)?;
+        if rest.starts_with(|ch: char| ch.is_alphanumeric() || ch == '_') {
+            return None;
+        }
+
+        ignored.and(Constant::new(word)).parse(rest)
+    }
+}
+
 fn token(token: &str, whitespace_end: bool) -> impl Parser<'_, Output = &'_ str> {
     TokenBase::new(token, whitespace_end).bind(|tk| ignored.and(Constant::new(tk)))
 }
 
 fn function_t(source: &str) -> Option<Result<'_, &str>> {
-    token("function", true).parse(source)
+    keyword("function").parse(source)
 }
 
 fn if_t(source: &str) -> Option<Result<'_, &str>> {
-    token("if", true).parse(source)
+    keyword("if").parse(source)
 }
 
 fn else_t(source: &str) -> Option<Result<'_, &str>> {
-    token("else", true).parse(source)
+    keyword("else").parse(source)
 }
 
 fn return_t(source: &str) -> Option<Result<'_, &str>> {
-    token("return", true).parse(source)
+    keyword("return").parse(source)
 }
 
 fn var_t(source: &str) -> Option<Result<'_, &str>> {
-    token("var", true).parse(source)
+    keyword("var").parse(source)
 }
 
 fn while_t(source: &str) -> Option<Result<'_, &str>> {
-    token("while", true).parse(source)
+    keyword("while").parse(source)
 }
 
 fn comma_t(source: &str) -> Option<Result<'_, &str>> {
     token(",", false).parse(source)
 }
 
+fn dot_t(source: &str) -> Option<Result<'_, &str>> {
+    token(".", false).parse(source)
+}
+
+fn colon_t(source: &str) -> Option<Result<'_, &str>> {
+    token(":", false).parse(source)
+}
+
 fn semicolon_t(source: &str) -> Option<Result<'_, &str>> {
     token(";", false).parse(source)
 }
@@ -389,10 +1521,18 @@ fn right_brace_t(source: &str) -> Option<Result<'_, &str>> {
     token("}", false).parse(source)
 }
 
-fn number_base(source: &str) -> Option<Result<'_, i64>> {
+fn left_bracket_t(source: &str) -> Option<Result<'_, &str>> {
+    token("[", false).parse(source)
+}
+
+fn right_bracket_t(source: &str) -> Option<Result<'_, &str>> {
+    token("]", false).parse(source)
+}
+
+fn digit_run(source: &str, radix: u32) -> Option<Result<'_, &str>> {
     let mut end = 0;
     for (idx, ch) in source.char_indices() {
-        if !ch.is_ascii_digit() {
+        if !ch.is_digit(radix) {
             break;
         }
 
@@ -403,18 +1543,152 @@ fn number_base(source: &str) -> Option<Result<'_, i64>> {
         None
     } else {
         Some(Result {
-            value: source[0..end].parse().unwrap(),
+            value: &source[0..end],
             source: &source[end..],
         })
     }
 }
 
+fn digits(source: &str, radix: u32) -> Option<Result<'_, i64>> {
+    let Result { value, source } = digit_run(source, radix)?;
+    Some(Result {
+        value: i64::from_str_radix(value, radix).ok()?,
+        source,
+    })
+}
+
+#[cfg(feature = "bigint")]
+fn big_digits(source: &str, radix: u32) -> Option<Result<'_, BigInt>> {
+    let Result { value, source } = digit_run(source, radix)?;
+    Some(Result {
+        value: BigInt::from_str_radix(value, radix)?,
+        source,
+    })
+}
+
+fn number_base(source: &str) -> Option<Result<'_, i64>> {
+    number_base_with(source, digits)
+}
+
+fn number_base_with<'a, T>(
+    source: &'a str,
+    digits: impl Fn(&'a str, u32) -> Option<Result<'a, T>>,
+) -> Option<Result<'a, T>> {
+    let config = context::number_config();
+    let prefixes = [
+        ("0x", 16, config.hex),
+        ("0o", 8, config.octal),
+        ("0b", 2, config.binary),
+    ];
+
+    for (prefix, radix, enabled) in prefixes {
+        if let Some(rest) = source.strip_prefix(prefix) {
+            return if enabled { digits(rest, radix) } else { None };
+        }
+    }
+
+    if config.decimal {
+        digits(source, 10)
+    } else {
+        None
+    }
+}
+
 fn number(source: &str) -> Option<Result<'_, i64>> {
     number_base
         .bind(|tk| ignored.and(Constant::new(tk)))
         .parse(source)
 }
 
+// The suffix has to make up a whole identifier, so `1u8x` isn't `1u8`
+// followed by `x`.
+fn typed_number(source: &str) -> Option<Result<'_, (i64, IntWidth)>> {
+    let Result { source, value } = number_base(source)?;
+    let suffix = id_base(source)?;
+    let width = IntWidth::from_suffix(suffix.value)?;
+    ignored
+        .and(Constant::new((value, width)))
+        .parse(suffix.source)
+}
+
+// Only tried once `number` failed, which for a well-formed literal means it
+// overflows an `i64`.
+#[cfg(feature = "bigint")]
+fn big_number(source: &str) -> Option<Result<'_, BigInt>> {
+    (|source| number_base_with(source, big_digits))
+        .bind(|tk| ignored.and(Constant::new(tk)))
+        .parse(source)
+}
+
+fn escape(source: &str) -> Option<Result<'_, char>> {
+    let rest = source.strip_prefix('\\')?;
+    let mut chars = rest.chars();
+    let value = match chars.next()? {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        '0' => '\0',
+        '\\' => '\\',
+        '\'' => '\'',
+        '"' => '"',
+        '$' => '$',
+        'x' => {
+            let hex = rest.get(1..3)?;
+            if !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+                return None;
+            }
+
+            let code = u8::from_str_radix(hex, 16).ok().filter(u8::is_ascii)?;
+            return Some(Result {
+                value: char::from(code),
+                source: &rest[3..],
+            });
+        }
+        _ => return None,
+    };
+
+    Some(Result {
+        value,
+        source: chars.as_str(),
+    })
+}
+
+fn char_literal_base(source: &str) -> Option<Result<'_, char>> {
+    let rest = source.strip_prefix('\'')?;
+    let Result { value, source } = match rest.chars().next()? {
+        '\\' => escape(rest)?,
+        '\'' | '\n' => return None,
+        ch => Result {
+            value: ch,
+            source: &rest[ch.len_utf8()..],
+        },
+    };
+
+    Some(Result {
+        value,
+        source: source.strip_prefix('\'')?,
+    })
+}
+
+fn char_literal(source: &str) -> Option<Result<'_, char>> {
+    char_literal_base
+        .bind(|tk| ignored.and(Constant::new(tk)))
+        .parse(source)
+}
+
+// A single, possibly escaped, character of a string literal. Fails on the
+// closing quote and on line breaks.
+fn string_char(source: &str) -> Option<Result<'_, char>> {
+    match source.chars().next()? {
+        '"' | '\n' => None,
+        '\\' => escape(source),
+        ch => Some(Result {
+            value: ch,
+            source: &source[ch.len_utf8()..],
+        }),
+    }
+}
+
 fn id_base(source: &str) -> Option<Result<'_, &str>> {
     let mut end = 0;
     for (idx, ch) in source.char_indices() {
@@ -440,39 +1714,48 @@ fn id_base(source: &str) -> Option<Result<'_, &str>> {
 }
 
 fn id(source: &str) -> Option<Result<'_, &str>> {
-    id_base
-        .bind(|tk| ignored.and(Constant::new(tk)))
-        .parse(source)
-}
+    let res = id_base(source)?;
+    if let Some(limit) = context::max_identifier_len()
+        && res.value.chars().count() > limit
+    {
+        context::fail(ParseError::identifier_too_long(source, limit));
+        return None;
+    }
 
-fn not_t(source: &str) -> Option<Result<'_, &str>> {
-    token("!", false).parse(source)
+    ignored.and(Constant::new(res.value)).parse(res.source)
 }
 
-fn equal_t(source: &str) -> Option<Result<'_, &str>> {
-    token("==", false).parse(source)
+// Sorted by descending length, so that an operator is never shadowed by one of
+// its prefixes.
+const OPERATORS: [&str; 17] = [
+    "**", "//", "==", "!=", "<<", ">>", "!", "+", "-", "*", "/", "%", "=", "&", "|", "^", "~",
+];
+
+fn operator_t(source: &str) -> Option<Result<'_, &str>> {
+    let operator = OPERATORS.iter().find(|op| source.starts_with(*op))?;
+    token(operator, false).parse(source)
 }
 
-fn not_equal_t(source: &str) -> Option<Result<'_, &str>> {
-    token("!=", false).parse(source)
+fn symbol<'a>(expected: &'static str) -> impl Parser<'a, Output = &'a str> {
+    move |source: &'a str| operator_t(source).filter(|res| res.value == expected)
 }
 
-fn plus_t(source: &str) -> Option<Result<'_, &str>> {
-    token("+", false).parse(source)
+fn not_t(source: &str) -> Option<Result<'_, &str>> {
+    symbol("!").parse(source)
 }
 
 fn minus_t(source: &str) -> Option<Result<'_, &str>> {
-    token("-", false).parse(source)
+    symbol("-").parse(source)
 }
 
-fn star_t(source: &str) -> Option<Result<'_, &str>> {
-    token("*", false).parse(source)
+fn star_star_t(source: &str) -> Option<Result<'_, &str>> {
+    symbol("**").parse(source)
 }
 
-fn slash_t(source: &str) -> Option<Result<'_, &str>> {
-    token("/", false).parse(source)
+fn assign_t(source: &str) -> Option<Result<'_, &str>> {
+    symbol("=").parse(source)
 }
 
-fn assign_t(source: &str) -> Option<Result<'_, &str>> {
-    token("=", false).parse(source)
+fn tilde_t(source: &str) -> Option<Result<'_, &str>> {
+    symbol("~").parse(source)
 }
@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use super::{Parser, Result};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleProfile {
+    pub rule: &'static str,
+    pub calls: usize,
+    pub total: Duration,
+}
+
+thread_local! {
+    static REGISTRY: RefCell<HashMap<&'static str, (usize, Duration)>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Timings of every profiled rule on this thread, slowest first. Nested rules
+/// are included in the total of the rules that contain them.
+pub fn profile_report() -> Vec<RuleProfile> {
+    let mut report: Vec<_> = REGISTRY.with_borrow(|registry| {
+        registry
+            .iter()
+            .map(|(rule, (calls, total))| RuleProfile {
+                rule,
+                calls: *calls,
+                total: *total,
+            })
+            .collect()
+    });
+
+    report.sort_by(|a, b| b.total.cmp(&a.total).then(a.rule.cmp(b.rule)));
+    report
+}
+
+pub fn reset_profile() {
+    REGISTRY.with_borrow_mut(|registry| registry.clear());
+}
+
+pub(super) struct Profiled<P> {
+    rule: &'static str,
+    parser: P,
+}
+
+impl<'a, P> Profiled<P>
+where
+    P: Parser<'a>,
+{
+    pub(super) fn new(rule: &'static str, parser: P) -> Self {
+        Profiled { rule, parser }
+    }
+}
+
+impl<'a, P> Parser<'a> for Profiled<P>
+where
+    P: Parser<'a>,
+{
+    type Output = P::Output;
+
+    fn parse(&self, source: &'a str) -> Option<Result<'a, Self::Output>> {
+        let start = Instant::now();
+        let res = self.parser.parse(source);
+        let elapsed = start.elapsed();
+
+        REGISTRY.with_borrow_mut(|registry| {
+            let (calls, total) = registry.entry(self.rule).or_default();
+            *calls += 1;
+            *total += elapsed;
+        });
+
+        res
+    }
+}
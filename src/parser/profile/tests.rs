@@ -0,0 +1,41 @@
+use crate::parser::{ParseOptions, parse_program};
+
+use super::*;
+
+#[test]
+fn profile_counts_rules() {
+    reset_profile();
+    parse_program("var x = 1; f(x);").unwrap();
+
+    let report = profile_report();
+    let statement = report
+        .iter()
+        .find(|profile| profile.rule == "statement")
+        .unwrap();
+    // Two statements and the failed attempt at the end of the input.
+    assert_eq!(statement.calls, 3);
+    assert!(
+        report
+            .iter()
+            .any(|profile| profile.rule == "atom" && profile.calls > 0)
+    );
+
+    reset_profile();
+    assert_eq!(profile_report(), vec![]);
+}
+
+#[test]
+fn profile_memoized_atom() {
+    reset_profile();
+    let options = ParseOptions {
+        memoize: true,
+        ..Default::default()
+    };
+    options.parse_program("x;").unwrap();
+
+    assert!(
+        profile_report()
+            .iter()
+            .any(|profile| profile.rule == "atom")
+    );
+}
@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod tests;
+
+use super::{TokenKind, highlight_tokens, parse_resilient};
+use crate::{ast::Node, span::Span};
+
+/// Parses `new_source`, an edit of `old_source`, reusing the top level items
+/// of `old` (the program parsed from `old_source`) that the edit didn't touch.
+///
+/// Only the items between the unchanged prefix and suffix of the two sources
+/// are parsed again. Whenever the items of the old source can't be matched up
+/// with `old`, the whole new source is parsed instead. As with
+/// `parse_resilient`, items that fail to parse become `Node::Error`.
+pub fn reparse(old: &Node, old_source: &str, new_source: &str) -> Node {
+    let Node::Block(old_items) = old else {
+        return parse_resilient(new_source).0;
+    };
+
+    let old_ranges = item_ranges(old_source);
+    if old_ranges.len() != old_items.len() {
+        return parse_resilient(new_source).0;
+    }
+
+    let new_ranges = item_ranges(new_source);
+    let prefix = common_prefix(old_source, new_source);
+    let suffix = common_suffix(old_source, new_source, prefix);
+    let shift = |span: Span| Span {
+        start: span.start + new_source.len() - old_source.len(),
+        end: span.end + new_source.len() - old_source.len(),
+    };
+
+    let leading = old_ranges
+        .iter()
+        .zip(&new_ranges)
+        .take_while(|(old, new)| old == new && old.end <= prefix)
+        .count();
+    let trailing = old_ranges[leading..]
+        .iter()
+        .rev()
+        .zip(new_ranges[leading..].iter().rev())
+        .take_while(|(old, new)| old.start >= old_source.len() - suffix && shift(**old) == **new)
+        .count();
+
+    let start = new_ranges
+        .get(leading)
+        .map_or(new_source.len(), |span| span.start);
+    let end = match trailing {
+        0 => new_source.len(),
+        _ => new_ranges[new_ranges.len() - trailing].start,
+    };
+
+    let (Node::Block(changed), errors) = parse_resilient(&new_source[start..end]) else {
+        unreachable!("parse_resilient always returns a block");
+    };
+    // An error may just as well come from cutting the source at the wrong
+    // place, so only the full parse can tell.
+    if !errors.is_empty() {
+        return parse_resilient(new_source).0;
+    }
+
+    let mut items = old_items[..leading].to_vec();
+    items.extend(changed);
+    items.extend_from_slice(&old_items[old_items.len() - trailing..]);
+    Node::Block(items)
+}
+
+// Splits the source into top level items by tracking brackets, without
+// parsing it. An item ends at a `;` or `}` outside of any brackets, unless the
+// next token continues it, as with `else` or a lambda that's called or used as
+// an operand.
+fn item_ranges(source: &str) -> Vec<Span> {
+    let tokens: Vec<_> = highlight_tokens(source)
+        .into_iter()
+        .filter(|(_, kind)| !matches!(kind, TokenKind::Whitespace | TokenKind::Comment))
+        .map(|(span, kind)| (span, &source[span.start..span.end], kind))
+        .collect();
+
+    let mut ranges = Vec::new();
+    let mut start = None;
+    let mut depth = 0usize;
+    for (idx, &(span, text, _)) in tokens.iter().enumerate() {
+        let item_start = *start.get_or_insert(span.start);
+        match text {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+
+        let ends = depth == 0
+            && match (text, tokens.get(idx + 1)) {
+                (_, None) => true,
+                (";", Some(&(_, next, _))) => next != "else",
+                ("}", Some(&(_, next, kind))) => !continues_block(next, kind),
+                _ => false,
+            };
+
+        if ends {
+            ranges.push(Span {
+                start: item_start,
+                end: span.end,
+            });
+            start = None;
+        }
+    }
+
+    ranges
+}
+
+fn continues_block(next: &str, kind: TokenKind) -> bool {
+    match kind {
+        TokenKind::Operator => !matches!(next, "!" | "~"),
+        TokenKind::Punctuation => matches!(next, ";" | "(" | "[" | "," | ")" | "]"),
+        _ => next == "else",
+    }
+}
+
+fn common_prefix(a: &str, b: &str) -> usize {
+    a.bytes().zip(b.bytes()).take_while(|(a, b)| a == b).count()
+}
+
+// Never overlaps the common prefix of length `prefix`.
+fn common_suffix(a: &str, b: &str, prefix: usize) -> usize {
+    a.bytes()
+        .rev()
+        .zip(b.bytes().rev())
+        .take(a.len().min(b.len()) - prefix)
+        .take_while(|(a, b)| a == b)
+        .count()
+}
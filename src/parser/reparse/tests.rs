@@ -0,0 +1,104 @@
+use crate::parser::parse_program;
+
+use super::*;
+
+const SOURCE: &str = "
+function a() { return 1; }
+function b() { return 2; }
+function c() { return 3; }
+";
+
+// Swaps the body of every function for a marker, so that reused items can be
+// told apart from reparsed ones.
+fn mark(program: &Node) -> Node {
+    let Node::Block(items) = program else {
+        panic!("program is not a block");
+    };
+
+    let items = items
+        .iter()
+        .map(|item| match item {
+            Node::Function(function) => Node::Function(crate::ast::Function {
+                body: Box::new(Node::Id("reused".to_string())),
+                ..function.clone()
+            }),
+            item => item.clone(),
+        })
+        .collect();
+    Node::Block(items)
+}
+
+fn items(program: Node) -> Vec<Node> {
+    match program {
+        Node::Block(items) => items,
+        _ => panic!("program is not a block"),
+    }
+}
+
+#[test]
+fn reparse_reuses_untouched_items() {
+    let old = mark(&parse_program(SOURCE).unwrap());
+    let new_source = SOURCE.replace("return 2;", "var x = 2; return x;");
+
+    let new = items(reparse(&old, SOURCE, &new_source));
+    let expected = items(parse_program(&new_source).unwrap());
+    let old = items(old);
+
+    assert_eq!(new.len(), 3);
+    assert_eq!(new[0], old[0]);
+    assert_eq!(new[1], expected[1]);
+    assert_eq!(new[2], old[2]);
+}
+
+#[test]
+fn reparse_matches_full_parse() {
+    for (old_source, new_source) in [
+        (
+            SOURCE,
+            SOURCE.replace("function b", "var x = 0;\nfunction b"),
+        ),
+        (SOURCE, SOURCE.replace("function b() { return 2; }\n", "")),
+        (SOURCE, format!("{SOURCE}print(a());")),
+        (SOURCE, format!("// header\n{SOURCE}")),
+        ("if (a) x; y; z;", "if (a) x; else y; z;".to_string()),
+        (
+            "var f = function() { }; f();",
+            "var f = function() { }(); f();".to_string(),
+        ),
+        ("a; b; c;", "a; b = 1 + ; c;".to_string()),
+    ] {
+        let old = parse_program(old_source).unwrap();
+        assert_eq!(
+            reparse(&old, old_source, &new_source),
+            parse_resilient(&new_source).0,
+            "{new_source}"
+        );
+    }
+}
+
+#[test]
+fn reparse_mismatched_old_program() {
+    let old = Node::Block(vec![]);
+    assert_eq!(
+        reparse(&old, SOURCE, SOURCE),
+        parse_program(SOURCE).unwrap()
+    );
+}
+
+#[test]
+fn item_ranges_follow_statements() {
+    let source = "if (a) { } else { } var f = function() { }(1); { } x;";
+    let ranges: Vec<_> = item_ranges(source)
+        .into_iter()
+        .map(|span| &source[span.start..span.end])
+        .collect();
+    assert_eq!(
+        ranges,
+        [
+            "if (a) { } else { }",
+            "var f = function() { }(1);",
+            "{ }",
+            "x;"
+        ]
+    );
+}
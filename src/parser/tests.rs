@@ -1,3 +1,5 @@
+use std::cell::Cell;
+
 use super::*;
 
 #[test]
@@ -203,6 +205,48 @@ fn token_unbreakable() {
     assert_eq!(token(",", true).parse("foo,"), None);
 }
 
+#[test]
+fn token_keyword() {
+    assert_eq!(
+        token("var", true).parse("var x"),
+        Some(Result {
+            source: "x",
+            value: "var",
+        })
+    );
+
+    assert_eq!(token("var", true).parse("variable"), None);
+}
+
+#[test]
+fn keyword_word_boundary() {
+    assert_eq!(
+        keyword("if").parse("if(x)"),
+        Some(Result {
+            source: "(x)",
+            value: "if",
+        })
+    );
+    assert_eq!(
+        keyword("if").parse("if /* c */ (x)"),
+        Some(Result {
+            source: "(x)",
+            value: "if",
+        })
+    );
+    assert_eq!(
+        keyword("if").parse("if"),
+        Some(Result {
+            source: "",
+            value: "if",
+        })
+    );
+    assert_eq!(keyword("if").parse("iffy"), None);
+    assert_eq!(keyword("if").parse("if_"), None);
+    assert_eq!(keyword("if").parse("if2"), None);
+    assert_eq!(keyword("if").parse(" if"), None);
+}
+
 #[test]
 fn number_empty() {
     assert_eq!(number.parse(""), None);
@@ -268,3 +312,1123 @@ fn id_valid() {
 fn id_invalid() {
     assert_eq!(id.parse("1foo"), None);
 }
+
+#[test]
+fn or_else_first_succeeds() {
+    let calls = Cell::new(0);
+    let parser = number.or_else(|| {
+        calls.set(calls.get() + 1);
+        number
+    });
+
+    assert_eq!(
+        parser.parse("123"),
+        Some(Result {
+            source: "",
+            value: 123,
+        })
+    );
+    assert_eq!(calls.get(), 0);
+}
+
+#[test]
+fn or_else_fallback() {
+    let calls = Cell::new(0);
+    let parser = id.or_else(|| {
+        calls.set(calls.get() + 1);
+        token("+", false)
+    });
+
+    assert_eq!(
+        parser.parse("+ foo"),
+        Some(Result {
+            source: "foo",
+            value: "+",
+        })
+    );
+    assert_eq!(calls.get(), 1);
+
+    assert_eq!(parser.parse("123"), None);
+    assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn statements_program() {
+    let mut statements = statements("var x = 1;\n// comment\nx = x + 1;\nprint(x);\n");
+
+    assert_eq!(
+        statements.next(),
+        Some(Ok(crate::ast::Node::Var(
+            "x".to_string(),
+            Box::new(crate::ast::Node::Number(1))
+        )))
+    );
+    assert_eq!(
+        statements.next(),
+        Some(Ok(crate::ast::Node::Assignment(
+            "x".to_string(),
+            Box::new(crate::ast::Node::Add(
+                Box::new(crate::ast::Node::Id("x".to_string())),
+                Box::new(crate::ast::Node::Number(1))
+            ))
+        )))
+    );
+    assert_eq!(
+        statements.next(),
+        Some(Ok(crate::ast::Node::Call {
+            callee: Box::new(crate::ast::Node::Id("print".to_string())),
+            args: vec![crate::ast::Node::Id("x".to_string())],
+        }))
+    );
+    assert_eq!(statements.next(), None);
+}
+
+#[test]
+fn statements_error() {
+    let mut statements = statements("var x = 1; var y = ; x = 3;");
+
+    assert!(matches!(statements.next(), Some(Ok(_))));
+    assert_eq!(
+        statements.next().map(|res| res.map_err(|error| error.kind)),
+        Some(Err(ParseErrorKind::Syntax))
+    );
+    assert_eq!(statements.next(), None);
+}
+
+#[test]
+fn statements_empty() {
+    assert_eq!(statements("  // nothing here\n").next(), None);
+}
+
+#[test]
+fn bitwise_tokens() {
+    assert_eq!(
+        symbol("&").parse("& x"),
+        Some(Result {
+            source: "x",
+            value: "&",
+        })
+    );
+    assert_eq!(
+        symbol("|").parse("|x"),
+        Some(Result {
+            source: "x",
+            value: "|",
+        })
+    );
+    assert_eq!(
+        symbol("^").parse("^ x"),
+        Some(Result {
+            source: "x",
+            value: "^",
+        })
+    );
+    assert_eq!(
+        tilde_t.parse("~x"),
+        Some(Result {
+            source: "x",
+            value: "~",
+        })
+    );
+}
+
+// There are no logical operators, so a doubled `&` or `|` is two tokens.
+#[test]
+fn bitwise_tokens_not_doubled() {
+    assert_eq!(
+        symbol("&").parse("&& x"),
+        Some(Result {
+            source: "& x",
+            value: "&",
+        })
+    );
+    assert_eq!(symbol("&&").parse("&& x"), None);
+    assert_eq!(symbol("||").parse("|| x"), None);
+}
+
+#[test]
+fn shift_tokens() {
+    assert_eq!(
+        symbol("<<").parse("<< x"),
+        Some(Result {
+            source: "x",
+            value: "<<",
+        })
+    );
+    assert_eq!(
+        symbol(">>").parse(">>x"),
+        Some(Result {
+            source: "x",
+            value: ">>",
+        })
+    );
+    assert_eq!(symbol("<<").parse("< x"), None);
+    assert_eq!(symbol(">>").parse("> x"), None);
+}
+
+#[test]
+fn opt_present_and_absent() {
+    assert_eq!(
+        number.opt().parse("42"),
+        Some(Result {
+            source: "",
+            value: Some(42),
+        })
+    );
+    assert_eq!(
+        number.opt().parse("foo"),
+        Some(Result {
+            source: "foo",
+            value: None,
+        })
+    );
+}
+
+#[test]
+fn cut_prevents_backtracking() {
+    let options = ParseOptions::default();
+    let parser = token("x", true).and(number).or(id.and(id).value(0));
+    assert_eq!(options.run(|| parser.parse_to_completion("x y")), Ok(0));
+
+    let parser = token("x", true).and(number.cut()).or(id.and(id).value(0));
+    assert_eq!(options.run(|| parser.parse_to_completion("x 1")), Ok(1));
+    assert_eq!(options.run(|| parser.parse_to_completion("y z")), Ok(0));
+    assert_eq!(
+        options.run(|| parser.parse_to_completion("x y")),
+        Err(ParseError {
+            kind: ParseErrorKind::Syntax,
+            offset: 2,
+            message: "unexpected `y`".to_string(),
+            expected: Vec::new(),
+            context: Vec::new(),
+        })
+    );
+}
+
+#[test]
+fn with_context_builds_rule_trail() {
+    let options = ParseOptions::default();
+    let expression = number.cut().with_context("expression");
+    let statement = token("return", true)
+        .and(expression)
+        .with_context("statement");
+    let body = left_brace_t
+        .and(statement.repeat(..))
+        .and(right_brace_t)
+        .with_context("function body");
+    let parser = function_t.and(body);
+
+    assert_eq!(
+        options.run(|| parser.parse_to_completion("function { return 1 return 2 }")),
+        Ok("}")
+    );
+
+    let error = options
+        .run(|| parser.parse_to_completion("function { return 1 return x }"))
+        .unwrap_err();
+    assert_eq!(
+        error.context,
+        vec!["function body", "statement", "expression"]
+    );
+    assert_eq!(
+        error.to_string(),
+        "while parsing function body → while parsing statement → \
+         while parsing expression → unexpected `x` at offset 27"
+    );
+
+    // Failing without a committed error leaves nothing to add context to.
+    let error = options
+        .run(|| parser.parse_to_completion("function { return 1 }}"))
+        .unwrap_err();
+    assert_eq!(error.context, Vec::<&str>::new());
+}
+
+#[test]
+fn choice_lists_expected_alternatives() {
+    let options = ParseOptions::default();
+    let operand = || {
+        choice(vec![
+            ("number", Box::new(number.value("number"))),
+            ("identifier", Box::new(id.value("identifier"))),
+            ("`(`", Box::new(left_paren_t.value("paren"))),
+        ])
+    };
+
+    assert_eq!(
+        operand().parse("x"),
+        Some(Result {
+            source: "",
+            value: "identifier"
+        })
+    );
+
+    let error = options
+        .run(|| operand().parse_to_completion("+"))
+        .unwrap_err();
+    assert_eq!(error.expected, vec!["number", "identifier", "`(`"]);
+    assert_eq!(
+        error.message,
+        "unexpected `+`, expected one of: number, identifier, `(`"
+    );
+
+    // Only names expected where the error occurs are listed.
+    let parser = operand().and(operand().cut());
+    let error = options
+        .run(|| parser.parse_to_completion("1 +"))
+        .unwrap_err();
+    assert_eq!(error.offset, 2);
+    assert_eq!(error.expected, vec!["number", "identifier", "`(`"]);
+
+    let error = options
+        .run(|| parser.parse_to_completion("1 2 3"))
+        .unwrap_err();
+    assert_eq!(error.offset, 4);
+    assert_eq!(error.expected, Vec::<&str>::new());
+}
+
+#[test]
+fn repeat_in_range() {
+    assert_eq!(
+        number.repeat(2..=4).parse("1 2 3 foo"),
+        Some(Result {
+            source: "foo",
+            value: vec![1, 2, 3],
+        })
+    );
+}
+
+#[test]
+fn repeat_below_minimum() {
+    assert_eq!(number.repeat(2..=4).parse("1 foo"), None);
+    assert_eq!(number.repeat(1..).parse("foo"), None);
+}
+
+#[test]
+fn repeat_capped_maximum() {
+    assert_eq!(
+        number.repeat(2..=4).parse("1 2 3 4 5 6"),
+        Some(Result {
+            source: "5 6",
+            value: vec![1, 2, 3, 4],
+        })
+    );
+
+    assert_eq!(
+        number.repeat(..2).parse("1 2"),
+        Some(Result {
+            source: "2",
+            value: vec![1],
+        })
+    );
+}
+
+#[test]
+fn repeat_empty_range() {
+    assert_eq!(number.repeat(..0).parse("1"), None);
+    assert_eq!(
+        number.repeat(..=0).parse("1"),
+        Some(Result {
+            source: "1",
+            value: vec![],
+        })
+    );
+}
+
+#[test]
+fn spanned_excludes_trailing_whitespace() {
+    assert_eq!(
+        Spanned::new(number).parse("123  foo"),
+        Some(Result {
+            source: "foo",
+            value: (Span { start: 0, end: 3 }, 123),
+        })
+    );
+}
+
+#[test]
+fn spanned_relative_to_input() {
+    let parser = id.and(Spanned::new(number));
+    assert_eq!(
+        parser.parse_to_completion("foo 42"),
+        Ok((Span { start: 4, end: 6 }, 42))
+    );
+}
+
+#[test]
+fn map_with_span() {
+    let parser = id.and(number.map_with_span(|value, span| (value, span.end - span.start)));
+    assert_eq!(
+        parser.parse("foo 1234 bar"),
+        Some(Result {
+            source: "bar",
+            value: (1234, 4),
+        })
+    );
+
+    let parser = id.and(number.map_with_span(|_, span| span));
+    assert_eq!(
+        parser.parse_to_completion("foo 42"),
+        Ok(Span { start: 4, end: 6 })
+    );
+}
+
+#[test]
+fn ws_skips_ignored_content() {
+    assert_eq!(
+        ws(number).parse("   42  "),
+        Some(Result {
+            source: "",
+            value: 42,
+        })
+    );
+    assert_eq!(
+        ws(char_literal_base).parse(" /* c */ 'a' // note\n x"),
+        Some(Result {
+            source: "x",
+            value: 'a',
+        })
+    );
+    assert_eq!(ws(number).parse("  foo"), None);
+    assert_eq!(
+        number.between_optional_whitespace().parse(" 7 ;"),
+        Some(Result {
+            source: ";",
+            value: 7,
+        })
+    );
+}
+
+#[test]
+fn recognize_expression() {
+    assert_eq!(
+        recognize(ast::expression).parse("f(1, 2) * (x + 3)  ; rest"),
+        Some(Result {
+            source: "; rest",
+            value: "f(1, 2) * (x + 3)",
+        })
+    );
+    assert_eq!(
+        recognize(number).parse("0x1f foo"),
+        Some(Result {
+            source: "foo",
+            value: "0x1f",
+        })
+    );
+    assert_eq!(recognize(number).parse("foo"), None);
+}
+
+#[test]
+fn count_array_exact() {
+    let parser = left_paren_t
+        .and(count_array::<3, _, _, _>(number, comma_t))
+        .bind(|point| right_paren_t.value(point));
+
+    assert_eq!(
+        parser.parse("(1, 2, 3) x"),
+        Some(Result {
+            source: "x",
+            value: [1, 2, 3],
+        })
+    );
+    assert_eq!(parser.parse("(1, 2)"), None);
+    assert_eq!(parser.parse("(1, 2, 3, 4)"), None);
+    assert_eq!(parser.parse("(1, 2, x)"), None);
+}
+
+#[test]
+fn sep_by_elements() {
+    assert_eq!(
+        sep_by(number, comma_t).parse("1, 2, 3 foo"),
+        Some(Result {
+            source: "foo",
+            value: vec![1, 2, 3],
+        })
+    );
+}
+
+#[test]
+fn sep_by_empty() {
+    assert_eq!(
+        sep_by(number, comma_t).parse("foo"),
+        Some(Result {
+            source: "foo",
+            value: vec![],
+        })
+    );
+}
+
+#[test]
+fn sep_by_trailing_separator() {
+    assert_eq!(
+        sep_by(number, comma_t).parse("1, 2, )"),
+        Some(Result {
+            source: ", )",
+            value: vec![1, 2],
+        })
+    );
+}
+
+#[test]
+fn sep_end_by_elements() {
+    let parser = sep_end_by(number, semicolon_t);
+
+    assert_eq!(
+        parser.parse("foo"),
+        Some(Result {
+            source: "foo",
+            value: vec![],
+        })
+    );
+    assert_eq!(
+        parser.parse("1; foo"),
+        Some(Result {
+            source: "foo",
+            value: vec![1],
+        })
+    );
+    assert_eq!(
+        parser.parse("1; 2;3; foo"),
+        Some(Result {
+            source: "foo",
+            value: vec![1, 2, 3],
+        })
+    );
+}
+
+#[test]
+fn sep_end_by_missing_separator() {
+    assert_eq!(sep_end_by(number, semicolon_t).parse("1; 2"), None);
+    assert_eq!(sep_end_by(number, semicolon_t).parse("1; 2 foo;"), None);
+}
+
+#[test]
+fn sep_by1_elements() {
+    let expected = Some(Result {
+        source: "foo",
+        value: vec![1, 2],
+    });
+
+    assert_eq!(sep_by1(number, comma_t).parse("1, 2 foo"), expected);
+    assert_eq!(number.many1_sep(comma_t).parse("1, 2 foo"), expected);
+}
+
+#[test]
+fn sep_by1_empty() {
+    assert_eq!(sep_by1(number, comma_t).parse("foo"), None);
+    assert_eq!(number.many1_sep(comma_t).parse("foo"), None);
+}
+
+#[test]
+fn sep_by1_trailing_separator() {
+    let expected = Some(Result {
+        source: ", )",
+        value: vec![1],
+    });
+
+    assert_eq!(sep_by1(number, comma_t).parse("1, )"), expected);
+    assert_eq!(number.many1_sep(comma_t).parse("1, )"), expected);
+}
+
+#[test]
+fn delimited_list_spans() {
+    let parser = id.and(delimited_list(left_paren_t, number, comma_t, right_paren_t));
+
+    assert_eq!(
+        parser.parse_to_completion("f(1, 22, 333)"),
+        Ok(vec![
+            (Span { start: 2, end: 3 }, 1),
+            (Span { start: 5, end: 7 }, 22),
+            (Span { start: 9, end: 12 }, 333),
+        ])
+    );
+}
+
+#[test]
+fn delimited_list_empty() {
+    assert_eq!(
+        delimited_list(left_paren_t, number, comma_t, right_paren_t).parse("() foo"),
+        Some(Result {
+            source: "foo",
+            value: vec![],
+        })
+    );
+}
+
+#[test]
+fn delimited_list_invalid() {
+    let parser = delimited_list(left_paren_t, number, comma_t, right_paren_t);
+
+    assert_eq!(parser.parse("(1, 2"), None);
+    assert_eq!(parser.parse("(1, )"), None);
+    assert_eq!(parser.parse("1, 2)"), None);
+}
+
+#[test]
+fn number_prefixed() {
+    assert_eq!(
+        number.parse("0x1F foo"),
+        Some(Result {
+            source: "foo",
+            value: 31,
+        })
+    );
+    assert_eq!(number.parse("0o17"), None);
+    assert_eq!(number.parse("0b101"), None);
+    assert_eq!(number.parse("0x"), None);
+}
+
+#[test]
+fn number_overflow() {
+    assert_eq!(number.parse("9223372036854775808"), None);
+}
+
+#[test]
+fn number_config_octal() {
+    let options = ParseOptions {
+        number: NumberConfig {
+            octal: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    assert_eq!(
+        options.parse_expression("0o17"),
+        Ok(crate::ast::Node::Number(15))
+    );
+    assert_eq!(
+        parse_expression("0o17").map_err(|error| error.kind),
+        Err(ParseErrorKind::Syntax)
+    );
+}
+
+#[test]
+fn number_config_disabled() {
+    let options = ParseOptions {
+        number: NumberConfig {
+            decimal: false,
+            hex: false,
+            octal: false,
+            binary: true,
+        },
+        ..Default::default()
+    };
+
+    assert_eq!(
+        options.parse_expression("0b101"),
+        Ok(crate::ast::Node::Number(5))
+    );
+    assert_eq!(
+        options.parse_expression("5").map_err(|error| error.kind),
+        Err(ParseErrorKind::Syntax)
+    );
+    assert_eq!(
+        options.parse_expression("0x5").map_err(|error| error.kind),
+        Err(ParseErrorKind::Syntax)
+    );
+}
+
+#[test]
+fn comment_style_hash() {
+    let options = ParseOptions {
+        comments: CommentStyle {
+            double_slash: false,
+            hash: true,
+            block: false,
+        },
+        ..Default::default()
+    };
+
+    assert_eq!(
+        options.parse_program("# comment\nx; # trailing"),
+        Ok(crate::ast::Node::Block(vec![crate::ast::Node::Id(
+            "x".to_string()
+        )]))
+    );
+    assert_eq!(
+        options
+            .parse_program("// comment\nx;")
+            .map_err(|error| error.kind),
+        Err(ParseErrorKind::Syntax)
+    );
+    assert_eq!(
+        options
+            .parse_program("/* comment */ x;")
+            .map_err(|error| error.kind),
+        Err(ParseErrorKind::Syntax)
+    );
+}
+
+#[test]
+fn comment_style_default() {
+    assert_eq!(
+        parse_program("// comment\n/* block */ x; // trailing"),
+        Ok(crate::ast::Node::Block(vec![crate::ast::Node::Id(
+            "x".to_string()
+        )]))
+    );
+    assert_eq!(
+        parse_program("# comment\nx;").map_err(|error| error.kind),
+        Err(ParseErrorKind::Syntax)
+    );
+}
+
+#[test]
+fn statements_comment_style() {
+    let options = ParseOptions {
+        comments: CommentStyle {
+            double_slash: false,
+            hash: true,
+            block: false,
+        },
+        ..Default::default()
+    };
+    let mut statements = options.statements("# c\nx;\n# d\n");
+
+    assert_eq!(
+        statements.next(),
+        Some(Ok(crate::ast::Node::Id("x".to_string())))
+    );
+    assert_eq!(statements.next(), None);
+}
+
+#[test]
+fn statements_recover_to_newline() {
+    let options = ParseOptions {
+        recover_to_newline: true,
+        ..Default::default()
+    };
+    let mut statements = options.statements("var x = ;\nx = 1;\nvar y = 2 2;");
+
+    assert_eq!(
+        statements.next().map(|res| res.map_err(|error| error.kind)),
+        Some(Err(ParseErrorKind::Syntax))
+    );
+    assert_eq!(
+        statements.next(),
+        Some(Ok(crate::ast::Node::Assignment(
+            "x".to_string(),
+            Box::new(crate::ast::Node::Number(1))
+        )))
+    );
+    assert_eq!(
+        statements.next().map(|res| res.map_err(|error| error.kind)),
+        Some(Err(ParseErrorKind::Syntax))
+    );
+    assert_eq!(statements.next(), None);
+}
+
+#[test]
+fn inspect_success() {
+    let seen = std::cell::RefCell::new(Vec::new());
+    let parser = ZeroOrMore::new(id.inspect(|id| seen.borrow_mut().push(id.to_string())));
+
+    assert_eq!(
+        parser.parse("foo bar 1"),
+        Some(Result {
+            source: "1",
+            value: vec!["foo", "bar"],
+        })
+    );
+    assert_eq!(*seen.borrow(), vec!["foo", "bar"]);
+}
+
+#[test]
+fn inspect_failure() {
+    let calls = Cell::new(0);
+    let parser = number.inspect(|_| calls.set(calls.get() + 1));
+
+    assert_eq!(parser.parse("foo"), None);
+    assert_eq!(calls.get(), 0);
+}
+
+fn counted_list(source: &str) -> Option<Result<'_, Vec<&str>>> {
+    let parser = number.then_with(|count| {
+        let count = count as usize;
+        let element = id.bind(|id| comma_t.opt().and(Constant::new(id)));
+
+        left_bracket_t
+            .and(element.repeat(count..=count))
+            .then_with(|ids| right_bracket_t.and(Constant::new(ids)))
+    });
+
+    parser.parse(source)
+}
+
+#[test]
+fn then_with_matching_count() {
+    assert_eq!(
+        counted_list("3[a, b, c] foo"),
+        Some(Result {
+            source: "foo",
+            value: vec!["a", "b", "c"],
+        })
+    );
+    assert_eq!(
+        counted_list("0[]"),
+        Some(Result {
+            source: "",
+            value: vec![],
+        })
+    );
+}
+
+#[test]
+fn then_with_count_mismatch() {
+    assert_eq!(counted_list("2[a, b, c]"), None);
+    assert_eq!(counted_list("4[a, b, c]"), None);
+}
+
+#[test]
+fn either_left() {
+    assert_eq!(
+        either(number, id).parse("42 foo"),
+        Some(Result {
+            source: "foo",
+            value: Either::Left(42),
+        })
+    );
+}
+
+#[test]
+fn either_right() {
+    assert_eq!(
+        either(number, id).parse("foo 42"),
+        Some(Result {
+            source: "42",
+            value: Either::Right("foo"),
+        })
+    );
+}
+
+#[test]
+fn either_none() {
+    assert_eq!(either(number, id).parse("+"), None);
+}
+
+#[test]
+fn char_literal_plain() {
+    assert_eq!(
+        char_literal.parse("'a' x"),
+        Some(Result {
+            source: "x",
+            value: 'a'
+        })
+    );
+}
+
+#[test]
+fn char_literal_escapes() {
+    assert_eq!(
+        char_literal.parse("'\\n'"),
+        Some(Result {
+            source: "",
+            value: '\n'
+        })
+    );
+    assert_eq!(
+        char_literal.parse("'\\''"),
+        Some(Result {
+            source: "",
+            value: '\''
+        })
+    );
+    assert_eq!(
+        char_literal.parse("'\\x41'"),
+        Some(Result {
+            source: "",
+            value: 'A'
+        })
+    );
+}
+
+#[test]
+fn char_literal_invalid() {
+    assert_eq!(char_literal.parse("''"), None);
+    assert_eq!(char_literal.parse("'ab'"), None);
+    assert_eq!(char_literal.parse("'\\q'"), None);
+    assert_eq!(char_literal.parse("'\\xff'"), None);
+}
+
+#[test]
+fn complete_consumes_everything() {
+    assert_eq!(
+        number.complete().parse("42"),
+        Some(Result {
+            source: "",
+            value: 42
+        })
+    );
+    assert_eq!(number.complete().parse("42x"), None);
+    assert_eq!(number.complete().parse("x"), None);
+}
+
+fn lowercase(source: &str) -> Option<Result<'_, char>> {
+    let ch = source.chars().next().filter(char::is_ascii_lowercase)?;
+    Some(Result {
+        source: &source[ch.len_utf8()..],
+        value: ch,
+    })
+}
+
+#[test]
+fn collect_into_string() {
+    assert_eq!(
+        lowercase.collect_into::<String>().parse("abcD"),
+        Some(Result {
+            source: "D",
+            value: "abc".to_string()
+        })
+    );
+    assert_eq!(
+        lowercase.collect_into::<String>().parse("1"),
+        Some(Result {
+            source: "1",
+            value: String::new()
+        })
+    );
+}
+
+#[test]
+fn collect_into_set() {
+    let parser = number.bind(|n| comma_t.opt().and(Constant::new(n)));
+
+    assert_eq!(
+        parser
+            .collect_into::<std::collections::BTreeSet<_>>()
+            .parse("3, 1, 3, 2")
+            .map(|res| res.value.into_iter().collect::<Vec<_>>()),
+        Some(vec![1, 2, 3])
+    );
+}
+
+#[test]
+fn flat_map_flattens_groups() {
+    // Groups of numbers, each ended by `;`.
+    let groups = || {
+        number
+            .repeat(1..)
+            .bind(|group| semicolon_t.value(group))
+            .repeat(..)
+    };
+
+    assert_eq!(
+        groups().flat_map(|group| group).parse("1 2; 3; 4 5 6; x"),
+        Some(Result {
+            source: "x",
+            value: vec![1, 2, 3, 4, 5, 6]
+        })
+    );
+    assert_eq!(
+        groups()
+            .flat_map(|group| group.into_iter().rev())
+            .parse("1 2; 3 4;"),
+        Some(Result {
+            source: "",
+            value: vec![2, 1, 4, 3]
+        })
+    );
+    assert_eq!(
+        groups().flat_map(|group| group).parse(""),
+        Some(Result {
+            source: "",
+            value: vec![]
+        })
+    );
+}
+
+#[test]
+fn iter_parse_from_saved_state() {
+    let input = "1 2 x";
+    let state = ParseState::new(input);
+
+    let (first, after_first) = number.iter_parse(state).unwrap();
+    assert_eq!(first, 1);
+    assert_eq!(after_first.offset(), 2);
+    assert_eq!(after_first.remaining(), "2 x");
+
+    // Parsing again from a saved state gives the same result as parsing
+    // its slice from the start.
+    let saved = after_first;
+    let (second, after_second) = number.iter_parse(saved).unwrap();
+    assert_eq!(
+        number.parse(saved.remaining()),
+        Some(Result {
+            source: after_second.remaining(),
+            value: second
+        })
+    );
+    assert_eq!(number.iter_parse(saved), Some((2, after_second)));
+
+    assert_eq!(number.iter_parse(after_second), None);
+    assert_eq!(id.iter_parse(after_second).map(|(name, _)| name), Some("x"));
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Op {
+    Add,
+    Subtract,
+}
+
+#[test]
+fn value_replaces_output() {
+    let parser = symbol("+")
+        .value(Op::Add)
+        .or(symbol("-").value(Op::Subtract));
+
+    assert_eq!(
+        parser.parse("+ 1"),
+        Some(Result {
+            source: "1",
+            value: Op::Add
+        })
+    );
+    assert_eq!(
+        parser.parse("-"),
+        Some(Result {
+            source: "",
+            value: Op::Subtract
+        })
+    );
+    assert_eq!(parser.parse("*"), None);
+}
+
+#[test]
+fn pair_keeps_both() {
+    assert_eq!(
+        id.pair(number).parse("foo 42;"),
+        Some(Result {
+            source: ";",
+            value: ("foo", 42)
+        })
+    );
+    assert_eq!(id.pair(number).parse("foo bar"), None);
+    assert_eq!(id.pair(number).parse("42"), None);
+}
+
+#[test]
+fn star_star_before_star() {
+    assert_eq!(
+        star_star_t.parse("** 2"),
+        Some(Result {
+            source: "2",
+            value: "**"
+        })
+    );
+    assert_eq!(symbol("*").parse("** 2"), None);
+    assert_eq!(star_star_t.parse("* 2"), None);
+}
+
+#[test]
+fn slash_slash_before_slash() {
+    assert_eq!(
+        symbol("//").parse("// 2"),
+        Some(Result {
+            source: "2",
+            value: "//"
+        })
+    );
+    assert_eq!(symbol("/").parse("// 2"), None);
+    assert_eq!(symbol("//").parse("/ 2"), None);
+}
+
+#[test]
+fn operators_longest_match() {
+    assert!(
+        OPERATORS
+            .windows(2)
+            .all(|pair| pair[0].len() >= pair[1].len())
+    );
+
+    for operator in OPERATORS.into_iter().filter(|op| op.len() > 1) {
+        assert_eq!(
+            operator_t.parse(&format!("{operator} x")),
+            Some(Result {
+                source: "x",
+                value: operator,
+            })
+        );
+        assert_eq!(symbol(&operator[..1]).parse(operator), None);
+    }
+}
+
+#[test]
+fn separated_pair_drops_separator() {
+    let parser = separated_pair(id, token("=", false), number);
+    assert_eq!(
+        parser.parse("x = 1;"),
+        Some(Result {
+            source: ";",
+            value: ("x", 1),
+        })
+    );
+    assert_eq!(parser.parse("x 1"), None);
+}
+
+#[test]
+fn within_window() {
+    assert_eq!(
+        within(3, number).parse("123 + 4"),
+        Some(Result {
+            source: " + 4",
+            value: 123,
+        })
+    );
+    assert_eq!(
+        within(3, number).parse("12+4"),
+        Some(Result {
+            source: "+4",
+            value: 12,
+        })
+    );
+    assert_eq!(
+        within(3, number).parse("7"),
+        Some(Result {
+            source: "",
+            value: 7,
+        })
+    );
+}
+
+#[test]
+fn within_overflowing_window() {
+    assert_eq!(within(3, number).parse("1234"), None);
+    assert_eq!(within(3, id).parse("abcd"), None);
+    assert_eq!(within(1, char_literal).parse("'a'"), None);
+    assert_eq!(within(1, id).parse("čd"), None);
+}
+
+#[test]
+fn peek_char_does_not_consume() {
+    let source = "ab";
+    assert_eq!(peek_char(source), Some('a'));
+    assert_eq!(source, "ab");
+    assert_eq!(peek_char("čd"), Some('č'));
+    assert_eq!(peek_char(""), None);
+}
+
+#[test]
+fn branch_on_char_class() {
+    let parser = branch(
+        |ch| ch.is_ascii_digit(),
+        number.map(Either::Left),
+        id.map(Either::Right),
+    );
+
+    assert_eq!(
+        parser.parse("42 x"),
+        Some(Result {
+            source: "x",
+            value: Either::Left(42),
+        })
+    );
+    assert_eq!(
+        parser.parse("x1 2"),
+        Some(Result {
+            source: "2",
+            value: Either::Right("x1"),
+        })
+    );
+    assert_eq!(parser.parse("+"), None);
+    assert_eq!(parser.parse(""), None);
+}
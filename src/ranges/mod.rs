@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod tests;
+
+use std::fmt::{self, Display};
+
+use crate::ast::Node;
+#[cfg(feature = "bigint")]
+use crate::bigint::BigInt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IntWidth {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl IntWidth {
+    const ALL: [IntWidth; 8] = [
+        IntWidth::I8,
+        IntWidth::I16,
+        IntWidth::I32,
+        IntWidth::I64,
+        IntWidth::U8,
+        IntWidth::U16,
+        IntWidth::U32,
+        IntWidth::U64,
+    ];
+
+    /// The width a literal suffix such as `u8` stands for.
+    pub fn from_suffix(suffix: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|width| width.suffix() == suffix)
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            IntWidth::I8 => "i8",
+            IntWidth::I16 => "i16",
+            IntWidth::I32 => "i32",
+            IntWidth::I64 => "i64",
+            IntWidth::U8 => "u8",
+            IntWidth::U16 => "u16",
+            IntWidth::U32 => "u32",
+            IntWidth::U64 => "u64",
+        }
+    }
+
+    fn contains(self, value: i64) -> bool {
+        match self {
+            IntWidth::I8 => i8::try_from(value).is_ok(),
+            IntWidth::I16 => i16::try_from(value).is_ok(),
+            IntWidth::I32 => i32::try_from(value).is_ok(),
+            IntWidth::I64 => true,
+            IntWidth::U8 => u8::try_from(value).is_ok(),
+            IntWidth::U16 => u16::try_from(value).is_ok(),
+            IntWidth::U32 => u32::try_from(value).is_ok(),
+            IntWidth::U64 => value >= 0,
+        }
+    }
+}
+
+impl Display for IntWidth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.suffix())
+    }
+}
+
+/// The value of an integer literal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Literal {
+    Int(i64),
+    /// A literal too large for an `i64`, which no width holds.
+    #[cfg(feature = "bigint")]
+    Big(BigInt),
+}
+
+impl Literal {
+    fn fits(&self, width: IntWidth) -> bool {
+        match self {
+            Literal::Int(value) => width.contains(*value),
+            #[cfg(feature = "bigint")]
+            Literal::Big(_) => false,
+        }
+    }
+
+    fn negate(self) -> Self {
+        match self {
+            Literal::Int(value) => Literal::Int(value.wrapping_neg()),
+            #[cfg(feature = "bigint")]
+            Literal::Big(value) => {
+                let value = &BigInt::from(0) - &value;
+                value.to_i64().map_or(Literal::Big(value), Literal::Int)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeError {
+    pub value: Literal,
+    pub target: IntWidth,
+}
+
+pub fn check_literal_ranges(node: &Node, target: IntWidth) -> Result<(), Vec<RangeError>> {
+    let mut errors = Vec::new();
+    collect(node, target, &mut errors);
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+// A literal with a suffix is checked against the width it names rather than
+// `target`.
+fn literal(node: &Node, target: IntWidth) -> Option<(Literal, IntWidth)> {
+    match node {
+        Node::Number(value) => Some((Literal::Int(*value), target)),
+        Node::TypedNumber { value, suffix } => Some((Literal::Int(*value), *suffix)),
+        #[cfg(feature = "bigint")]
+        Node::BigNumber(value) => Some((Literal::Big(value.clone()), target)),
+        _ => None,
+    }
+}
+
+// A negated literal is checked as the negative value it stands for, so `-128`
+// fits an `i8`.
+fn collect(node: &Node, target: IntWidth, errors: &mut Vec<RangeError>) {
+    let checked = match node {
+        Node::Negate(operand) => {
+            literal(operand, target).map(|(value, target)| (value.negate(), target))
+        }
+        node => literal(node, target),
+    };
+
+    let Some((value, target)) = checked else {
+        for child in node.children() {
+            collect(child, target, errors);
+        }
+        return;
+    };
+
+    if !value.fits(target) {
+        errors.push(RangeError { value, target });
+    }
+}
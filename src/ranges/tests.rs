@@ -0,0 +1,163 @@
+use crate::parser::parse_program;
+
+use super::*;
+
+#[test]
+fn literal_fits() {
+    let program = parse_program("var x = 2147483647; return x + 1;").unwrap();
+    assert_eq!(check_literal_ranges(&program, IntWidth::I32), Ok(()));
+}
+
+#[test]
+fn literal_overflows() {
+    let program = parse_program("function f() { return [3000000000, 1, 2147483648]; }").unwrap();
+    assert_eq!(
+        check_literal_ranges(&program, IntWidth::I32),
+        Err(vec![
+            RangeError {
+                value: Literal::Int(3000000000),
+                target: IntWidth::I32,
+            },
+            RangeError {
+                value: Literal::Int(2147483648),
+                target: IntWidth::I32,
+            },
+        ])
+    );
+    assert_eq!(check_literal_ranges(&program, IntWidth::I64), Ok(()));
+}
+
+#[test]
+fn literal_suffix_sets_width() {
+    let program = parse_program("return [255u8, 256u8, 3000000000, -1u64];").unwrap();
+    assert_eq!(
+        check_literal_ranges(&program, IntWidth::I64),
+        Err(vec![
+            RangeError {
+                value: Literal::Int(256),
+                target: IntWidth::U8,
+            },
+            RangeError {
+                value: Literal::Int(-1),
+                target: IntWidth::U64,
+            },
+        ])
+    );
+    assert_eq!(
+        check_literal_ranges(&program, IntWidth::I32),
+        Err(vec![
+            RangeError {
+                value: Literal::Int(256),
+                target: IntWidth::U8,
+            },
+            RangeError {
+                value: Literal::Int(3000000000),
+                target: IntWidth::I32,
+            },
+            RangeError {
+                value: Literal::Int(-1),
+                target: IntWidth::U64,
+            },
+        ])
+    );
+}
+
+#[test]
+fn literal_narrow_widths() {
+    let program = parse_program("return 128 + 255;").unwrap();
+    assert_eq!(check_literal_ranges(&program, IntWidth::I16), Ok(()));
+    assert_eq!(
+        check_literal_ranges(&program, IntWidth::I8),
+        Err(vec![
+            RangeError {
+                value: Literal::Int(128),
+                target: IntWidth::I8,
+            },
+            RangeError {
+                value: Literal::Int(255),
+                target: IntWidth::I8,
+            },
+        ])
+    );
+}
+
+#[test]
+fn literal_negated() {
+    let program = parse_program("return [-128, -(1), -129];").unwrap();
+    assert_eq!(
+        check_literal_ranges(&program, IntWidth::I8),
+        Err(vec![RangeError {
+            value: Literal::Int(-129),
+            target: IntWidth::I8,
+        }])
+    );
+
+    let program = parse_program("return -2147483648 + -2147483649;").unwrap();
+    assert_eq!(
+        check_literal_ranges(&program, IntWidth::I32),
+        Err(vec![RangeError {
+            value: Literal::Int(-2147483649),
+            target: IntWidth::I32,
+        }])
+    );
+}
+
+#[test]
+fn literal_signed_bounds() {
+    let bounds = [
+        (IntWidth::I8, i64::from(i8::MIN), i64::from(i8::MAX)),
+        (IntWidth::I16, i64::from(i16::MIN), i64::from(i16::MAX)),
+        (IntWidth::I32, i64::from(i32::MIN), i64::from(i32::MAX)),
+    ];
+    for (width, min, max) in bounds {
+        let program = parse_program(&format!("return [{min}, {max}];")).unwrap();
+        assert_eq!(check_literal_ranges(&program, width), Ok(()));
+
+        let program = parse_program(&format!("return [{}, {}];", min - 1, max + 1)).unwrap();
+        assert_eq!(
+            check_literal_ranges(&program, width),
+            Err(vec![
+                RangeError {
+                    value: Literal::Int(min - 1),
+                    target: width,
+                },
+                RangeError {
+                    value: Literal::Int(max + 1),
+                    target: width,
+                },
+            ])
+        );
+    }
+}
+
+// The `i64` minimum is only written as the negation of a big literal.
+#[cfg(feature = "bigint")]
+#[test]
+fn literal_i64_bounds() {
+    let program = parse_program("return [-9223372036854775808, 9223372036854775807];").unwrap();
+    assert_eq!(check_literal_ranges(&program, IntWidth::I64), Ok(()));
+
+    let program = parse_program("return -9223372036854775809;").unwrap();
+    let big = BigInt::from_str_radix("9223372036854775809", 10).unwrap();
+    assert_eq!(
+        check_literal_ranges(&program, IntWidth::I64),
+        Err(vec![RangeError {
+            value: Literal::Big(&BigInt::from(0) - &big),
+            target: IntWidth::I64,
+        }])
+    );
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn literal_big_number() {
+    let program = parse_program("return 1 + 18446744073709551616;").unwrap();
+    let big = BigInt::from_str_radix("18446744073709551616", 10).unwrap();
+    assert_eq!(
+        check_literal_ranges(&program, IntWidth::I64),
+        Err(vec![RangeError {
+            value: Literal::Big(big),
+            target: IntWidth::I64,
+        }])
+    );
+}
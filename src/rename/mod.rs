@@ -0,0 +1,137 @@
+#[cfg(test)]
+mod tests;
+
+use std::collections::{HashMap, HashSet, hash_map::Entry};
+
+use crate::ast::{Function, Node};
+
+/// Gives every binding in the program a name no other binding has, and
+/// rewrites the references to it, so that the program runs the same with a
+/// single flat namespace.
+///
+/// The first binding of a name keeps it, later ones get a numbered suffix that
+/// isn't used anywhere in the program. Variables are function scoped, so a
+/// name declared anywhere in a function refers to that declaration throughout
+/// the function, nested blocks included. Names without a binding, like
+/// builtins, are left alone.
+pub fn alpha_rename(mut node: Node) -> Node {
+    let mut taken = HashSet::new();
+    node.collect_names(&mut taken);
+
+    let mut pass = Pass {
+        taken,
+        bound: HashSet::new(),
+        scopes: Vec::new(),
+    };
+    pass.scope(&mut [], &mut node);
+    node
+}
+
+struct Pass {
+    // Every name in the program, and every one given out since.
+    taken: HashSet<String>,
+    // Names some binding already kept.
+    bound: HashSet<String>,
+    // Maps names declared in each enclosing function to their new names,
+    // innermost last.
+    scopes: Vec<HashMap<String, String>>,
+}
+
+impl Pass {
+    // Renames a function body, or the whole program, together with the
+    // parameters it binds.
+    fn scope(&mut self, parameters: &mut [String], body: &mut Node) {
+        let mut declared = parameters.to_vec();
+        declarations(body, &mut declared);
+
+        let mut scope = HashMap::new();
+        for name in declared {
+            if let Entry::Vacant(entry) = scope.entry(name) {
+                let unique = self.unique(entry.key());
+                entry.insert(unique);
+            }
+        }
+
+        for parameter in parameters.iter_mut() {
+            *parameter = scope[parameter].clone();
+        }
+
+        self.scopes.push(scope);
+        self.rename(body);
+        self.scopes.pop();
+    }
+
+    fn unique(&mut self, name: &str) -> String {
+        if self.bound.insert(name.to_string()) {
+            return name.to_string();
+        }
+
+        let mut suffix = 1;
+        loop {
+            let unique = format!("{name}_{suffix}");
+            if self.taken.insert(unique.clone()) {
+                return unique;
+            }
+            suffix += 1;
+        }
+    }
+
+    fn resolve(&self, name: &mut String) {
+        let renamed = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name.as_str()));
+        if let Some(renamed) = renamed {
+            *name = renamed.clone();
+        }
+    }
+
+    fn rename(&mut self, node: &mut Node) {
+        match node {
+            Node::Id(name) | Node::Var(name, _) | Node::Assignment(name, _) => self.resolve(name),
+            Node::Function(Function {
+                name,
+                parameters,
+                body,
+            }) => {
+                self.resolve(name);
+                self.scope(parameters, body);
+                return;
+            }
+            Node::Lambda { parameters, body } => {
+                self.scope(parameters, body);
+                return;
+            }
+            Node::Destructure { pattern, .. } => {
+                for name in pattern {
+                    self.resolve(name);
+                }
+            }
+            _ => (),
+        }
+
+        for child in node.children_mut() {
+            self.rename(child);
+        }
+    }
+}
+
+// Names `node` declares in the enclosing function, leaving out the ones
+// nested functions declare for themselves.
+fn declarations(node: &Node, declared: &mut Vec<String>) {
+    match node {
+        Node::Var(name, _) => declared.push(name.clone()),
+        Node::Destructure { pattern, .. } => declared.extend(pattern.iter().cloned()),
+        Node::Function(Function { name, .. }) => {
+            declared.push(name.clone());
+            return;
+        }
+        Node::Lambda { .. } => return,
+        _ => (),
+    }
+
+    for child in node.children() {
+        declarations(child, declared);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{
+    interpreter::{Interpreter, Value},
+    parser::parse_program,
+};
+
+use super::*;
+
+fn rename(source: &str) -> String {
+    let Node::Block(statements) = alpha_rename(parse_program(source).unwrap()) else {
+        panic!("program is not a block");
+    };
+
+    statements
+        .iter()
+        .map(|node| {
+            if node.is_statement() {
+                node.to_string()
+            } else {
+                format!("{node};")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn run(source: &str) -> Value {
+    let program = parse_program(source).unwrap();
+    let expected = Interpreter::new().run(&program).unwrap();
+    let renamed = Interpreter::new().run(&alpha_rename(program)).unwrap();
+    assert_eq!(renamed, expected);
+    renamed
+}
+
+#[test]
+fn renames_shadowing_variable() {
+    let source = "var x = 1; function f() { var x = 2; return x; } return x * 10 + f();";
+    assert_eq!(
+        rename(source),
+        "var x = 1; function f() { var x_1 = 2; return x_1; } return x * 10 + f();"
+    );
+    assert_eq!(run(source), Value::Number(12));
+}
+
+#[test]
+fn renames_parameters() {
+    let source = "
+        var x = 1;
+        function add(x, y) { return function(x) { return x + y; }(x * 10); }
+        return add(x + 1, 3) + x;
+    ";
+    assert_eq!(
+        rename(source),
+        "var x = 1; \
+         function add(x_1, y) { return function(x_2) { return x_2 + y; }(x_1 * 10); } \
+         return add(x + 1, 3) + x;"
+    );
+    assert_eq!(run(source), Value::Number(24));
+}
+
+#[test]
+fn variables_are_function_scoped() {
+    // Both `var x` in `f` declare the same variable, which `x = x + 1` and the
+    // final `return x` refer to as well.
+    let source = "
+        var x = 100;
+        function f(n) {
+            if (n) { var x = 1; } else { var x = 2; }
+            x = x + 1;
+            return x;
+        }
+        return f(1) * 10 + f(0) + x;
+    ";
+    assert_eq!(
+        rename(source),
+        "var x = 100; \
+         function f(n) { if (n) { var x_1 = 1; } else { var x_1 = 2; } x_1 = x_1 + 1; return x_1; } \
+         return f(1) * 10 + f(0) + x;"
+    );
+    assert_eq!(run(source), Value::Number(123));
+}
+
+#[test]
+fn avoids_existing_names() {
+    let source = "
+        var x_1 = 5;
+        function g(x) { return x + x_1; }
+        var x = 1;
+        return g(x) + len([x]);
+    ";
+    assert_eq!(
+        rename(source),
+        "var x_1 = 5; function g(x_2) { return x_2 + x_1; } var x = 1; return g(x) + len([x]);"
+    );
+    assert_eq!(run(source), Value::Number(7));
+}
+
+#[test]
+fn renames_nested_functions() {
+    let source = "
+        function f(n) {
+            function f(m) { return m * 2; }
+            return f(n);
+        }
+        return f(4);
+    ";
+    assert_eq!(
+        rename(source),
+        "function f(n) { function f_1(m) { return m * 2; } return f_1(n); } return f(4);"
+    );
+    assert_eq!(run(source), Value::Number(8));
+}
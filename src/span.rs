@@ -0,0 +1,51 @@
+#[cfg(test)]
+mod tests;
+
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// One-based line and column of a byte offset, with columns counted in
+/// characters. A tab may span several columns, see `with_tab_width`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    /// Locates `offset` in `source`. Offsets past the end map to the end.
+    pub fn new(source: &str, offset: usize) -> Self {
+        Self::with_tab_width(source, offset, 1)
+    }
+
+    /// Like `new`, but a tab advances the column to the next multiple of
+    /// `tab_width`, the way editors display it. A width of 0 is taken as 1.
+    pub fn with_tab_width(source: &str, offset: usize, tab_width: usize) -> Self {
+        let tab_width = tab_width.max(1);
+        let mut offset = offset.min(source.len());
+        while !source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        Position {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().fold(0, |column, ch| match ch {
+                '\t' => (column / tab_width + 1) * tab_width,
+                _ => column + 1,
+            }) + 1,
+        }
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
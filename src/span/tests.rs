@@ -0,0 +1,48 @@
+use super::*;
+
+#[test]
+fn position_lines_and_columns() {
+    let source = "ab\ncčd\n";
+    assert_eq!(Position::new(source, 0), Position { line: 1, column: 1 });
+    assert_eq!(Position::new(source, 2), Position { line: 1, column: 3 });
+    assert_eq!(Position::new(source, 3), Position { line: 2, column: 1 });
+    assert_eq!(Position::new(source, 6), Position { line: 2, column: 3 });
+    assert_eq!(Position::new(source, 100), Position { line: 3, column: 1 });
+    assert_eq!(Position::new(source, 2).to_string(), "1:3");
+}
+
+#[test]
+fn position_tabs() {
+    let source = "\tab\t\tc\n  \td";
+    assert_eq!(Position::new(source, 3), Position { line: 1, column: 4 });
+    assert_eq!(Position::new(source, 6), Position { line: 1, column: 7 });
+    assert_eq!(
+        Position::with_tab_width(source, 6, 1),
+        Position::new(source, 6)
+    );
+
+    assert_eq!(
+        Position::with_tab_width(source, 1, 4),
+        Position { line: 1, column: 5 }
+    );
+    assert_eq!(
+        Position::with_tab_width(source, 3, 4),
+        Position { line: 1, column: 7 }
+    );
+    // `ab` ends at column 6, so the tabs stop at 9 and 13.
+    assert_eq!(
+        Position::with_tab_width(source, 5, 4),
+        Position {
+            line: 1,
+            column: 13
+        }
+    );
+    assert_eq!(
+        Position::with_tab_width(source, 10, 4),
+        Position { line: 2, column: 5 }
+    );
+    assert_eq!(
+        Position::with_tab_width(source, 10, 0),
+        Position::new(source, 10)
+    );
+}
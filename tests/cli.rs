@@ -0,0 +1,63 @@
+use std::process::{Command, Output};
+
+fn funpiler(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_funpiler"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn prints_ast() {
+    let output = funpiler(&["tests/fixtures/sample.fun"]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("Block(\n"), "{stdout}");
+    assert!(stdout.contains("name: \"sum\""), "{stdout}");
+    assert!(
+        stdout.contains("Id(\n                \"print\","),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn reports_parse_error_position() {
+    let output = funpiler(&["tests/fixtures/invalid.fun"]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "tests/fixtures/invalid.fun:2:9: unexpected `(`\n"
+    );
+}
+
+#[test]
+fn reports_usage() {
+    let output = funpiler(&[]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "usage: funpiler [check] <file.fun>\n"
+    );
+}
+
+#[test]
+fn check_valid() {
+    let output = funpiler(&["check", "tests/fixtures/sample.fun"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn check_invalid() {
+    let output = funpiler(&["check", "tests/fixtures/invalid_many.fun"]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "tests/fixtures/invalid_many.fun:1:7: unexpected `1`\n\
+         tests/fixtures/invalid_many.fun:2:9: unexpected `(`\n"
+    );
+}